@@ -5,13 +5,22 @@ const AUTHOR: &str = env!("CARGO_PKG_AUTHORS");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const LICENSE: &str = env!("CARGO_PKG_LICENSE");
 const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
+/// Environment variable with the directory of the paper files, used when `--library` is not given.
+pub const LIBRARY_ENV: &str = "DUMPLING_LIBRARY";
+/// Environment variable with the path to the configuration file, used when `--config` is not given.
+pub const CONFIG_ENV: &str = "DUMPLING_CONFIG";
 
 /// Summary of boolean program arguments.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ProgFlags {
     pub open: bool,
     pub list_tags: bool,
+    pub list: bool,
+    pub stats: bool,
     pub pdf_diagnostic: bool,
+    pub list_trash: bool,
+    pub check_config: bool,
+    pub dry_run: bool,
     pub auto: bool,
 }
 
@@ -29,6 +38,14 @@ pub struct ProgArgs {
     pub tags: Vec<String>,
     pub flags: ProgFlags,
     pub filter_by_tag: String,
+    pub library: String,
+    pub config: String,
+    pub restore: Vec<String>,
+    pub purge_trash: Option<u32>,
+    pub rename_tags: Vec<String>,
+    pub rename_into: String,
+    pub columns: Vec<String>,
+    pub format: String,
 }
 
 impl Default for ProgFlags {
@@ -36,7 +53,12 @@ impl Default for ProgFlags {
         ProgFlags {
             open: false,
             list_tags: false,
+            list: false,
+            stats: false,
             pdf_diagnostic: false,
+            list_trash: false,
+            check_config: false,
+            dry_run: false,
             auto: false,
         }
     }
@@ -55,20 +77,69 @@ impl Default for ProgArgs {
             tags: Vec::new(),
             flags: ProgFlags::default(),
             filter_by_tag: String::new(),
+            library: String::new(),
+            config: String::new(),
+            restore: Vec::new(),
+            purge_trash: None,
+            rename_tags: Vec::new(),
+            rename_into: String::new(),
+            columns: Vec::new(),
+            format: String::new(),
         }
     }
 }
 
 impl ProgArgs {
-    /// Check if struct is any different from the default.
+    /// Check if struct is any different from the default. The library and configuration
+    /// locations, the listed columns, the output format and the options of --rename-tag are
+    /// not taken into account, as they do not describe a paper.
     pub fn is_default(&self) -> bool {
-        if *self == Self::default() {
+        let default = ProgArgs {
+            library: self.library.clone(),
+            config: self.config.clone(),
+            columns: self.columns.clone(),
+            format: self.format.clone(),
+            rename_into: self.rename_into.clone(),
+            flags: ProgFlags {
+                dry_run: self.flags.dry_run,
+                ..ProgFlags::default()
+            },
+            ..Self::default()
+        };
+        if *self == default {
             return true;
         } else {
             return false;
         }
     }
 
+    /// Get the library asked for, the name or directory given by `--library`, otherwise
+    /// the one in the `DUMPLING_LIBRARY` environment variable. If neither is set, None
+    /// is returned.
+    pub fn library_spec(&self) -> Option<String> {
+        return location(&self.library, LIBRARY_ENV);
+    }
+
+    /// Get the path to the configuration file. This is the file given by `--config`,
+    /// otherwise the one in the `DUMPLING_CONFIG` environment variable, otherwise
+    /// $HOME/.config/dumpling/dumpling.toml.
+    pub fn config_path(&self) -> std::path::PathBuf {
+        match location(&self.config, CONFIG_ENV) {
+            Some(p) => return file::loader::expand_filepath(&std::path::PathBuf::from(p)),
+            None => {}
+        }
+        let mut config_path = match dirs::config_dir() {
+            Some(p) => p,
+            None => {
+                log::error!("Could not obtain $HOME/.config/ as `PathBuf`");
+                std::process::exit(1);
+            }
+        };
+        config_path.push(NAME);
+        config_path.push(format!("{}.toml", NAME));
+        return config_path;
+    }
+
     /// Convert subset of `ProgArgs` fields into `Paper` struct.
     pub fn to_paper(&self) -> Option<file::parser::Paper> {
         // Check of the program arguments are any different from the default, i.e. if the user
@@ -82,7 +153,7 @@ impl ProgArgs {
         let mut tag_vec: Vec<file::parser::Tag> = Vec::new();
 
         for val in self.authors.iter() {
-            let author: file::parser::Author = file::parser::Author { name: val.clone() };
+            let author: file::parser::Author = file::parser::Author::parse(val);
             author_vec.push(author);
         }
         for val in self.tags.iter() {
@@ -98,6 +169,10 @@ impl ProgArgs {
             description: self.description.clone(),
             bibtex: self.bibtex.clone(),
             docname: self.docname.clone(),
+            status: String::new(),
+            added: chrono::Local::now()
+                .format(file::parser::ADDED_FORMAT)
+                .to_string(),
             authors: author_vec,
             tags: tag_vec,
         };
@@ -105,6 +180,18 @@ impl ProgArgs {
     }
 }
 
+/// Get the value given by a program `option`, or if it is empty, by the environment
+/// variable `env_var`. If neither is set, None is returned.
+fn location(option: &str, env_var: &str) -> Option<String> {
+    if !option.is_empty() {
+        return Some(option.to_string());
+    }
+    match std::env::var(env_var) {
+        Ok(v) if !v.is_empty() => return Some(v),
+        _ => return None,
+    }
+}
+
 /// Using the `getopts` crate, the program arguments are parsed into the
 /// `ProgArgs` struct. Any arguments not provided take a default value
 /// as described by the Default implementation on `ProgArgs`.
@@ -144,11 +231,61 @@ pub fn parse_arguments() -> ProgArgs {
     opts.optopt(
         "",
         "filter-tag",
-        "Filter the papers by a tag. This only does something if the TUI is opened.",
+        "Filter the papers by a tag. This only does something if the TUI is opened or the papers are listed with --list.",
         "STRING (in double quotes)",
     );
 
+    opts.optopt(
+        "",
+        "library",
+        "Library to use, the name of a library in the configuration file or the directory of the paper files. Defaults to $DUMPLING_LIBRARY, or the default library if that is not set.",
+        "NAME|DIR",
+    );
+    opts.optopt(
+        "",
+        "config",
+        "Path to the configuration file. Defaults to $DUMPLING_CONFIG, or $HOME/.config/dumpling/dumpling.toml if that is not set.",
+        "FILE",
+    );
+    opts.optopt(
+        "",
+        "purge-trash",
+        "Permanently delete the papers that were moved to the trash at least DAYS days ago. Use 0 to empty the trash.",
+        "DAYS",
+    );
+
+    opts.optopt(
+        "",
+        "format",
+        "Output format of --list, --list-tags and --pdf-diagnose: text, json, csv or tsv, and of --stats: text or json. Defaults to text.",
+        "FORMAT",
+    );
+    opts.optopt(
+        "",
+        "into",
+        "New name of the tag(s) given with --rename-tag.",
+        "TAG",
+    );
+
     // Multi opts
+    opts.optmulti(
+        "",
+        "restore",
+        "Restore a paper from the trash, given its ID as shown by --list-trash. This flag can be used multiple times.",
+        "ID",
+    );
+    opts.optmulti(
+        "",
+        "rename-tag",
+        "Rename a tag in all paper files to the tag given with --into, tags below it move along. This flag can be used multiple times to merge several tags into one.",
+        "TAG",
+    );
+    opts.optmulti(
+        "",
+        "columns",
+        "Columns shown by --list, separated by commas: id, citekey, year, author, title, journal, tags, docname and status. Defaults to id,year,author,title,tags.",
+        "LIST",
+    );
     opts.optmulti(
         "a",
         "author",
@@ -165,7 +302,32 @@ pub fn parse_arguments() -> ProgArgs {
     // Boolean flags
     opts.optflag("o", "open", "Open the TUI.");
     opts.optflag("", "list-tags", "Print all the tags used to the terminal.");
+    opts.optflag(
+        "",
+        "list",
+        "Print the papers as a table to the terminal, filtered by --filter-tag.",
+    );
+    opts.optflag(
+        "",
+        "stats",
+        "Print statistics of the library: papers per year, journal and tag, top authors, papers without PDF or BibTeX and papers added per month.",
+    );
     opts.optflag("", "pdf-diagnose", "Show the file paths to all the invalid PDF links in the paper files and all the unused existing PDF files.");
+    opts.optflag(
+        "",
+        "list-trash",
+        "Show the papers that were deleted and moved to the trash.",
+    );
+    opts.optflag(
+        "",
+        "check-config",
+        "Check the configuration file and show every unknown key, wrong type and invalid color in it, with its line and column.",
+    );
+    opts.optflag(
+        "",
+        "dry-run",
+        "Together with --rename-tag, only show the papers that would be changed.",
+    );
     opts.optflag(
         "",
         "auto",
@@ -295,7 +457,78 @@ pub fn parse_arguments() -> ProgArgs {
             }
         };
     }
+    // Check if library is present
+    if matches.opt_present("library") {
+        prog_args.library = match matches.opt_str("library") {
+            Some(s) => s,
+            None => {
+                log::error!("Error obtaining argument from --library.");
+                std::process::exit(1);
+            }
+        };
+    }
+    // Check if config is present
+    if matches.opt_present("config") {
+        prog_args.config = match matches.opt_str("config") {
+            Some(s) => s,
+            None => {
+                log::error!("Error obtaining argument from --config.");
+                std::process::exit(1);
+            }
+        };
+    }
+    // Check if purge-trash is present
+    if matches.opt_present("purge-trash") {
+        let res = match matches.opt_str("purge-trash") {
+            Some(s) => s,
+            None => {
+                log::error!("Error obtaining argument from --purge-trash.");
+                std::process::exit(1);
+            }
+        };
+        prog_args.purge_trash = match res.parse::<u32>() {
+            Ok(n) => Some(n),
+            Err(err) => {
+                log::error!(
+                    "Error parsing argument of --purge-trash into 32-bit unsigned integer: {err}"
+                );
+                std::process::exit(1);
+            }
+        };
+    }
+    // Check if into is present
+    if matches.opt_present("into") {
+        prog_args.rename_into = match matches.opt_str("into") {
+            Some(s) => s,
+            None => {
+                log::error!("Error obtaining argument from --into.");
+                std::process::exit(1);
+            }
+        };
+    }
+    // Check if format is present
+    if matches.opt_present("format") {
+        prog_args.format = match matches.opt_str("format") {
+            Some(s) => s,
+            None => {
+                log::error!("Error obtaining argument from --format.");
+                std::process::exit(1);
+            }
+        };
+    }
     // Multi opts
+    // Check if papers to restore were provided
+    if matches.opt_present("restore") {
+        prog_args.restore = matches.opt_strs("restore");
+    }
+    // Check if tags to rename were provided
+    if matches.opt_present("rename-tag") {
+        prog_args.rename_tags = matches.opt_strs("rename-tag");
+    }
+    // Check if columns were provided
+    if matches.opt_present("columns") {
+        prog_args.columns = matches.opt_strs("columns");
+    }
     // Check if authors were provided
     if matches.opt_present("a") {
         prog_args.authors = matches.opt_strs("a");
@@ -314,9 +547,30 @@ pub fn parse_arguments() -> ProgArgs {
     if matches.opt_present("list-tags") {
         prog_args.flags.list_tags = !prog_args.flags.list_tags;
     }
+    if matches.opt_present("list") {
+        prog_args.flags.list = !prog_args.flags.list;
+    }
+    if matches.opt_present("stats") {
+        prog_args.flags.stats = !prog_args.flags.stats;
+    }
     if matches.opt_present("pdf-diagnose") {
         prog_args.flags.pdf_diagnostic = !prog_args.flags.pdf_diagnostic;
     }
+    if matches.opt_present("list-trash") {
+        prog_args.flags.list_trash = !prog_args.flags.list_trash;
+    }
+    if matches.opt_present("check-config") {
+        prog_args.flags.check_config = !prog_args.flags.check_config;
+    }
+    if matches.opt_present("dry-run") {
+        prog_args.flags.dry_run = !prog_args.flags.dry_run;
+    }
+    // The options of --rename-tag do nothing on their own.
+    if prog_args.rename_tags.is_empty() && (matches.opt_present("into") || prog_args.flags.dry_run)
+    {
+        println!("--into and --dry-run can only be used together with --rename-tag.");
+        std::process::exit(1);
+    }
     if matches.opt_present("version") {
         print_version();
     }
@@ -372,7 +626,9 @@ pub fn extract_bibtex_fields(
             std::process::exit(1);
         }
     };
-    let author_re = match regex::Regex::new(r"(?i)author\s*=\s*\{([^}]+)\}") {
+    // The author field can contain braces itself, e.g. {Barnes and Noble}, so only the
+    // start of the field is matched, the end is found by `braced_value`.
+    let author_re = match regex::Regex::new(r"(?i)\bauthor\s*=\s*\{") {
         Ok(r) => r,
         Err(err) => {
             log::error!("Could not create author regex: {err}");
@@ -425,42 +681,30 @@ pub fn extract_bibtex_fields(
         }
     };
     // Get the authors string
-    let authors_str: Option<String> = {
-        if author_re.is_match(bibtex) {
-            let caps = author_re.captures(bibtex);
-            match caps {
-                Some(c) => match c.get(1) {
-                    Some(s) => Some(s.as_str().to_string()),
-                    None => None,
-                },
-                None => None,
-            }
-        } else {
-            None
-        }
-    };
-    // We assume the authors field follows to expected format:
-    // author={Doe, John and Smith, Jane and ...} and extract from
-    // that ["John Doe", "Jane Smith", ...]
-    let authors: Option<Vec<String>> = match authors_str {
-        Some(s) => Some(
-            // Split into ["Doe, John", "Smith, Jane", ...]
-            s.split(" and ")
-                // For each name (e.g. Doe, John) split into first and last name (e.g. ["Doe", "John"])
-                .map(|name| {
-                    let parts: Vec<&str> = name.split(',').map(|s| s.trim()).collect();
-                    if parts.len() == 2 {
-                        format!("{} {}", parts[1], parts[0])
-                    } else {
-                        // If the name does not fit the expected format, just use it as is.
-                        name.to_string()
-                    }
-                })
-                .collect(),
-        ),
+    let authors_str: Option<String> = match author_re.find(bibtex) {
+        Some(m) => braced_value(&bibtex[m.end()..]),
         None => None,
     };
+    // Split the authors field, e.g. {Doe, John and van der Waals, J. D. and others}, into
+    // the names as written in BibTeX, e.g. ["Doe, John", "van der Waals, J. D.", "others"].
+    // The names are split into their parts when the paper is created.
+    let authors: Option<Vec<String>> = authors_str.map(|s| file::names::split_names(&s));
 
     // Return the parsed fields
     return (title, year, journal, authors);
 }
+
+/// Get the contents of a BibTeX field value up to the closing brace that matches the opening
+/// brace in front of `rest`, so nested braces are kept. Returns None if there is no such brace.
+fn braced_value(rest: &str) -> Option<String> {
+    let mut depth: usize = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(rest[..i].to_string()),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    return None;
+}
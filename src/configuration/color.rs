@@ -0,0 +1,162 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+
+/// Themes that are shipped with the program, selectable by name with the
+/// `theme` option of the [colors] section.
+pub const BUILTIN_THEMES: [(&str, &str); 5] = [
+    ("default", include_str!("themes/default.toml")),
+    ("terminal", include_str!("themes/terminal.toml")),
+    ("rose-pine-moon", include_str!("themes/rose-pine-moon.toml")),
+    ("gruvbox-dark", include_str!("themes/gruvbox-dark.toml")),
+    ("nord", include_str!("themes/nord.toml")),
+];
+
+/// A color as written in the configuration file. A color can be given as an
+/// `[r, g, b]` array, a hex code like `"#3e8fb0"` or `"#38b"`, an ANSI color name
+/// like `"lightblue"`, an ANSI color index like `"208"`, or `"default"` for the
+/// default color of the terminal. Anything else is kept as `Invalid`, so a single
+/// malformed color does not make the whole configuration file unreadable.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColorValue {
+    Valid(Color),
+    Invalid(String),
+}
+
+/// Parse a color written as a string, see `ColorValue` for the accepted forms.
+pub fn parse_color(spec: &str) -> Option<Color> {
+    let spec = spec.trim().to_lowercase();
+    match spec.as_str() {
+        "default" | "terminal" | "reset" | "none" => return Some(Color::Reset),
+        _ => {}
+    }
+    // Expand the short hex form, e.g. `#38b` into `#3388bb`.
+    if let Some(hex) = spec.strip_prefix('#') {
+        if hex.len() == 3 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            let expanded: String = hex.chars().flat_map(|c| [c, c]).collect();
+            return Color::from_str(&format!("#{}", expanded)).ok();
+        }
+    }
+    return Color::from_str(&spec).ok();
+}
+
+impl<'de> Deserialize<'de> for ColorValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = toml::Value::deserialize(deserializer)?;
+        let color = match &value {
+            toml::Value::String(s) => parse_color(s),
+            toml::Value::Array(rgb) if rgb.len() == 3 => {
+                let rgb: Vec<u8> = rgb
+                    .iter()
+                    .filter_map(|c| c.as_integer())
+                    .filter_map(|c| u8::try_from(c).ok())
+                    .collect();
+                if rgb.len() == 3 {
+                    Some(Color::Rgb(rgb[0], rgb[1], rgb[2]))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        match color {
+            Some(c) => return Ok(ColorValue::Valid(c)),
+            None => return Ok(ColorValue::Invalid(value.to_string())),
+        }
+    }
+}
+
+impl Serialize for ColorValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            ColorValue::Valid(Color::Reset) => return serializer.serialize_str("default"),
+            ColorValue::Valid(c) => return serializer.serialize_str(&c.to_string()),
+            ColorValue::Invalid(s) => return serializer.serialize_str(s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color_value(toml: &str) -> ColorValue {
+        let table: toml::Table = toml::from_str(&format!("color = {}", toml)).unwrap();
+        return ColorValue::deserialize(table["color"].clone()).unwrap();
+    }
+
+    #[test]
+    fn parse_valid_colors() {
+        assert_eq!(parse_color("#3e8fb0"), Some(Color::Rgb(0x3e, 0x8f, 0xb0)));
+        assert_eq!(parse_color("#38B"), Some(Color::Rgb(0x33, 0x88, 0xbb)));
+        assert_eq!(parse_color("LightBlue"), Some(Color::LightBlue));
+        assert_eq!(parse_color(" red "), Some(Color::Red));
+        assert_eq!(parse_color("208"), Some(Color::Indexed(208)));
+        for name in ["default", "terminal", "reset", "none"] {
+            assert_eq!(parse_color(name), Some(Color::Reset));
+        }
+    }
+
+    #[test]
+    fn parse_invalid_colors() {
+        for spec in ["", "#12345", "#ggg", "#3e8fb0ff", "blueish", "256", "-1"] {
+            assert_eq!(parse_color(spec), None, "{:?}", spec);
+        }
+    }
+
+    #[test]
+    fn deserialize_colors() {
+        assert_eq!(
+            color_value("[62, 143, 176]"),
+            ColorValue::Valid(Color::Rgb(62, 143, 176))
+        );
+        assert_eq!(
+            color_value("\"#fff\""),
+            ColorValue::Valid(Color::Rgb(255, 255, 255))
+        );
+        assert_eq!(
+            color_value("[1, 2, 300]"),
+            ColorValue::Invalid(String::from("[1, 2, 300]"))
+        );
+        assert_eq!(
+            color_value("[1, 2]"),
+            ColorValue::Invalid(String::from("[1, 2]"))
+        );
+        assert_eq!(color_value("12"), ColorValue::Invalid(String::from("12")));
+        assert_eq!(
+            color_value("\"nope\""),
+            ColorValue::Invalid(String::from("\"nope\""))
+        );
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        for spec in ["\"#3e8fb0\"", "\"default\"", "\"lightblue\"", "\"208\""] {
+            let value = color_value(spec);
+            let serialized = toml::Value::try_from(&value).unwrap().to_string();
+            assert_eq!(color_value(&serialized), value);
+        }
+    }
+
+    #[test]
+    fn builtin_themes_are_valid() {
+        for (name, contents) in BUILTIN_THEMES {
+            let table: toml::Table = toml::from_str(contents).unwrap();
+            for (key, value) in table {
+                let color = ColorValue::deserialize(value).unwrap();
+                assert!(
+                    matches!(color, ColorValue::Valid(_)),
+                    "{} in theme {}",
+                    key,
+                    name
+                );
+            }
+        }
+    }
+}
@@ -1,5 +1,9 @@
+use super::color::{ColorValue, BUILTIN_THEMES};
+use super::diagnostic::{parse_theme, validate, Diagnostic};
+use crate::key::binding::KeyBindings;
 use ratatui::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::io::Read;
 
 /// Summary of the `[general]` section of the configuration file.
@@ -11,13 +15,20 @@ use std::io::Read;
 /// in the configuration file, however, a direct path will need to be
 /// provided as the program cannot interpret the $HOME and ~ aliases.
 #[derive(Deserialize, Serialize, Debug, Clone)]
-#[serde(default)]
+#[serde(default, deny_unknown_fields)]
 pub struct GeneralFromFile {
     pub pdf_viewer: String,
     pub pdf_dir: String, // Cannot contain $HOME or ~, must be a direct path
     pub selection_icon: String,
     pub file_icon: String,
+    pub mark_icon: String,
     pub editor_command: String,
+    pub live_reload: bool,
+    pub key_sequence_timeout: u64,
+    pub status_timeout: u64,
+    pub desktop_notifications: bool,
+    pub clipboard_notifications: bool,
+    pub default_library: String,
 }
 
 impl Default for GeneralFromFile {
@@ -42,55 +53,51 @@ impl Default for GeneralFromFile {
             pdf_dir: String::from(pdf_dir),
             selection_icon: String::from("→  "),
             file_icon: String::from("F  "),
+            mark_icon: String::from("*  "),
             editor_command: String::from("kitty --detach nvim"),
+            live_reload: true,
+            key_sequence_timeout: 1000,
+            status_timeout: 3000,
+            desktop_notifications: false,
+            clipboard_notifications: true,
+            default_library: String::new(),
         }
     }
 }
 
 /// Summary of the [colors] section of the configuration file.
 ///
-/// Any field not specified by the configuration takes a default as
-/// specified by the Default implementation.
-#[derive(Deserialize, Serialize, Debug, Clone)]
-#[serde(default)]
+/// The `theme` field selects a theme file from the `themes` directory next to
+/// the configuration file, or a built-in theme, see `BUILTIN_THEMES`. Any color
+/// specified in this section is used instead of the one of the theme, any color
+/// that is not specified, or is invalid, is taken from the theme, or from the
+/// Default implementation of `TuiColors` if there is no theme.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
 pub struct ColorsFromFile {
-    pub master_block_title: Vec<u8>,
-    pub master_block_border: Vec<u8>,
-    pub explorer_unselected_fg: Vec<u8>,
-    pub explorer_unselected_bg: Vec<u8>,
-    pub explorer_selected_fg: Vec<u8>,
-    pub explorer_selected_bg: Vec<u8>,
-    pub content_block_title: Vec<u8>,
-    pub content_block_border: Vec<u8>,
-    pub popup_block_title: Vec<u8>,
-    pub popup_block_border: Vec<u8>,
-    pub popup_text: Vec<u8>,
-    pub title_content: Vec<u8>,
-    pub author_content: Vec<u8>,
-    pub description_content: Vec<u8>,
-    pub tag_content: Vec<u8>,
-}
-
-impl Default for ColorsFromFile {
-    fn default() -> Self {
-        ColorsFromFile {
-            master_block_title: vec![255, 255, 255],
-            master_block_border: vec![255, 255, 255],
-            explorer_unselected_fg: vec![0, 0, 255],
-            explorer_unselected_bg: vec![0, 0, 0],
-            explorer_selected_fg: vec![0, 0, 255],
-            explorer_selected_bg: vec![48, 48, 48],
-            content_block_title: vec![255, 255, 255],
-            content_block_border: vec![255, 255, 255],
-            popup_block_title: vec![255, 255, 255],
-            popup_block_border: vec![255, 0, 0],
-            popup_text: vec![255, 255, 255],
-            title_content: vec![255, 255, 255],
-            author_content: vec![255, 255, 255],
-            description_content: vec![255, 255, 255],
-            tag_content: vec![255, 255, 255],
-        }
-    }
+    pub theme: Option<String>,
+    pub master_block_title: Option<ColorValue>,
+    pub master_block_border: Option<ColorValue>,
+    pub explorer_unselected_fg: Option<ColorValue>,
+    pub explorer_unselected_bg: Option<ColorValue>,
+    pub explorer_selected_fg: Option<ColorValue>,
+    pub explorer_selected_bg: Option<ColorValue>,
+    pub explorer_marked_fg: Option<ColorValue>,
+    pub content_block_title: Option<ColorValue>,
+    pub content_block_border: Option<ColorValue>,
+    pub popup_block_title: Option<ColorValue>,
+    pub popup_block_border: Option<ColorValue>,
+    pub popup_text: Option<ColorValue>,
+    pub title_content: Option<ColorValue>,
+    pub author_content: Option<ColorValue>,
+    pub description_content: Option<ColorValue>,
+    pub tag_content: Option<ColorValue>,
+    pub status_success_fg: Option<ColorValue>,
+    pub status_error_fg: Option<ColorValue>,
+    pub bibtex_entry_type: Option<ColorValue>,
+    pub bibtex_key: Option<ColorValue>,
+    pub bibtex_field: Option<ColorValue>,
+    pub bibtex_value: Option<ColorValue>,
 }
 
 /// To use the colors provided by `ColorsFromFile`, they need to be
@@ -104,6 +111,7 @@ pub struct TuiColors {
     pub explorer_unselected_bg: Color,
     pub explorer_selected_fg: Color,
     pub explorer_selected_bg: Color,
+    pub explorer_marked_fg: Color,
     pub content_block_title: Color,
     pub content_block_border: Color,
     pub popup_block_title: Color,
@@ -113,46 +121,139 @@ pub struct TuiColors {
     pub author_content: Color,
     pub description_content: Color,
     pub tag_content: Color,
+    pub status_success_fg: Color,
+    pub status_error_fg: Color,
+    pub bibtex_entry_type: Color,
+    pub bibtex_key: Color,
+    pub bibtex_field: Color,
+    pub bibtex_value: Color,
 }
 
-/// Macro that takes an instance of `ColorFromFile`, the `TuiColors`
-/// type and all the field names. It repeatedly puts the RGB contents
-/// stored in the Vec<u8> type into the `Color::Rgb` function to
-/// generate a `ratatui::style::Color`. It does so for all the field
-/// names provided. This way it generates a new instance of `TuiColors`
-/// which is returned at the end.
-macro_rules! convert_colors {
-    // Take ColorFromFile instance, TuiColors type and all the
-    // field names as input. Repeat the same conversion operation
-    // on all the given fields. Create a new TuiColors struct
-    // by doing so.
-    ($src:ident, $dst:ident, $( $field:ident ),+) => {
-        $dst {
-            $(
-                $field: Color::Rgb(
-                    $src.$field[0],
-                    $src.$field[1],
-                    $src.$field[2],
-                ),
-            )+
+impl Default for TuiColors {
+    fn default() -> Self {
+        TuiColors {
+            master_block_title: Color::Rgb(255, 255, 255),
+            master_block_border: Color::Rgb(255, 255, 255),
+            explorer_unselected_fg: Color::Rgb(0, 0, 255),
+            explorer_unselected_bg: Color::Rgb(0, 0, 0),
+            explorer_selected_fg: Color::Rgb(0, 0, 255),
+            explorer_selected_bg: Color::Rgb(48, 48, 48),
+            explorer_marked_fg: Color::Rgb(255, 255, 0),
+            content_block_title: Color::Rgb(255, 255, 255),
+            content_block_border: Color::Rgb(255, 255, 255),
+            popup_block_title: Color::Rgb(255, 255, 255),
+            popup_block_border: Color::Rgb(255, 0, 0),
+            popup_text: Color::Rgb(255, 255, 255),
+            title_content: Color::Rgb(255, 255, 255),
+            author_content: Color::Rgb(255, 255, 255),
+            description_content: Color::Rgb(255, 255, 255),
+            tag_content: Color::Rgb(255, 255, 255),
+            status_success_fg: Color::Rgb(0, 255, 0),
+            status_error_fg: Color::Rgb(255, 0, 0),
+            bibtex_entry_type: Color::Rgb(255, 0, 255),
+            bibtex_key: Color::Rgb(255, 255, 0),
+            bibtex_field: Color::Rgb(0, 255, 255),
+            bibtex_value: Color::Rgb(0, 255, 0),
         }
+    }
+}
+
+/// Problem found while converting the [colors] section into `TuiColors`, see
+/// `TuiColors::from_config_file_colors`.
+///
+/// The `key` field contains the key of the [colors] section the problem is
+/// about, e.g. `popup_text` for an invalid color or `theme` for a theme that
+/// cannot be found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorProblem {
+    pub key: String,
+    pub message: String,
+}
+
+/// Macro that takes an instance of `ColorsFromFile`, an instance of
+/// `TuiColors`, a vector to collect the invalid colors in and all the field
+/// names. For every field that is specified in the `ColorsFromFile`, the color
+/// of the `TuiColors` is replaced by it. Invalid colors are skipped, so the
+/// color that was already there stays in use, and pushed to the vector together
+/// with the name of their field.
+macro_rules! apply_colors {
+    // Take ColorsFromFile instance, TuiColors instance, the vector of invalid
+    // colors and all the field names as input. Repeat the same operation on
+    // all the given fields.
+    ($src:ident, $dst:ident, $invalid:ident, $( $field:ident ),+) => {
+        $(
+            match &$src.$field {
+                Some(ColorValue::Valid(color)) => $dst.$field = *color,
+                Some(ColorValue::Invalid(value)) => {
+                    $invalid.push((stringify!($field), value.clone()));
+                }
+                None => {}
+            }
+        )+
     };
 }
 
 impl TuiColors {
     /// Conversion from `ColorsFromFile` to `TuiColors` since the
     /// program needs `ratatui::style::Color` for rendering the TUI.
-    /// The conversion uses the `convert_colors` macro.
-    pub fn from_config_file_colors(cff: &ColorsFromFile) -> Self {
-        let tui_colors = convert_colors!(
+    /// The colors of the theme, if one is set, are applied on top of
+    /// the default colors, and the colors of `cff` on top of those.
+    /// Theme files are looked for in `theme_dir` first. The invalid colors
+    /// and a theme that cannot be found are returned as `ColorProblem`s.
+    pub fn from_config_file_colors(
+        cff: &ColorsFromFile,
+        theme_dir: &std::path::PathBuf,
+    ) -> (Self, Vec<ColorProblem>) {
+        let mut tui_colors = TuiColors::default();
+        let mut problems: Vec<ColorProblem> = Vec::new();
+        if let Some(name) = &cff.theme {
+            match load_theme(name, theme_dir) {
+                Some(theme) => {
+                    for (field, value) in tui_colors.apply(&theme) {
+                        problems.push(ColorProblem {
+                            key: String::from("theme"),
+                            message: format!(
+                                "invalid color {} for `{}` in theme {:?}, the default is used",
+                                value, field, name
+                            ),
+                        });
+                    }
+                }
+                None => problems.push(ColorProblem {
+                    key: String::from("theme"),
+                    message: format!(
+                        "theme {:?} could not be found, the default colors are used",
+                        name
+                    ),
+                }),
+            }
+        }
+        for (field, value) in tui_colors.apply(cff) {
+            problems.push(ColorProblem {
+                key: field.to_string(),
+                message: format!(
+                    "invalid color {} for `colors.{}`, the color of the theme or the default is used",
+                    value, field
+                ),
+            });
+        }
+        return (tui_colors, problems);
+    }
+    /// Replace the colors by the ones specified in `cff`, using the `apply_colors` macro.
+    /// The fields with an invalid color are returned together with that color.
+    fn apply(&mut self, cff: &ColorsFromFile) -> Vec<(&'static str, String)> {
+        let mut invalid: Vec<(&'static str, String)> = Vec::new();
+        apply_colors!(
             cff,
-            TuiColors,
+            self,
+            invalid,
             master_block_title,
             master_block_border,
             explorer_unselected_fg,
             explorer_unselected_bg,
             explorer_selected_fg,
             explorer_selected_bg,
+            explorer_marked_fg,
             content_block_title,
             content_block_border,
             popup_block_title,
@@ -161,42 +262,242 @@ impl TuiColors {
             title_content,
             author_content,
             description_content,
-            tag_content
+            tag_content,
+            status_success_fg,
+            status_error_fg,
+            bibtex_entry_type,
+            bibtex_key,
+            bibtex_field,
+            bibtex_value
         );
-        return tui_colors;
+        return invalid;
+    }
+}
+
+/// Load the theme called `name`. A theme file `name.toml` inside `theme_dir`
+/// takes precedence over a built-in theme with the same name. A theme file
+/// contains the same color fields as the [colors] section, without a header.
+pub fn load_theme(name: &str, theme_dir: &std::path::PathBuf) -> Option<ColorsFromFile> {
+    let mut theme_path = theme_dir.clone();
+    theme_path.push(format!("{}.toml", name));
+    let contents = if theme_path.exists() {
+        match std::fs::read_to_string(&theme_path) {
+            Ok(c) => c,
+            Err(err) => {
+                log::warn!("Unable to read theme file {theme_path:?}: {err}");
+                return None;
+            }
+        }
+    } else {
+        match BUILTIN_THEMES.iter().find(|(builtin, _)| *builtin == name) {
+            Some((_, c)) => c.to_string(),
+            None => return None,
+        }
+    };
+    match parse_theme(&contents) {
+        Ok((theme, diagnostics)) => {
+            for diagnostic in diagnostics.iter() {
+                log::warn!("Theme {name:?}: {diagnostic}");
+            }
+            return Some(theme);
+        }
+        Err(diagnostic) => {
+            log::warn!("Unable to parse theme {name:?}: {diagnostic}");
+            return None;
+        }
     }
 }
 
 /// Summary of the [keybinds] section of the configuration file.
 ///
 /// Any field not specified by the configuration takes a default as
-/// specified by the Default implementation.
+/// specified by the Default implementation. Each action can be bound
+/// to several key sequences, see `KeySequence::parse` for the notation.
 #[derive(Deserialize, Serialize, Debug, Clone)]
-#[serde(default)]
+#[serde(default, deny_unknown_fields)]
 pub struct KeybindsFromFile {
-    pub quit: char,
-    pub next: char,
-    pub previous: char,
-    pub bibtex_to_clipboard: char,
-    pub edit: char,
-    pub delete: char,
-    pub open_in_pdfviewer: char,
+    pub quit: KeyBindings,
+    pub next: KeyBindings,
+    pub previous: KeyBindings,
+    pub page_down: KeyBindings,
+    pub page_up: KeyBindings,
+    pub half_page_down: KeyBindings,
+    pub half_page_up: KeyBindings,
+    pub go_to_top: KeyBindings,
+    pub go_to_bottom: KeyBindings,
+    pub go_to_index: KeyBindings,
+    pub bibtex_to_clipboard: KeyBindings,
+    pub edit: KeyBindings,
+    pub edit_field: KeyBindings,
+    pub edit_tags: KeyBindings,
+    pub delete: KeyBindings,
+    pub undo_delete: KeyBindings,
+    pub open_in_pdfviewer: KeyBindings,
+    pub toggle_mark: KeyBindings,
+    pub visual_mark: KeyBindings,
+    pub clear_marks: KeyBindings,
+    pub set_status: KeyBindings,
+    pub copy_citation_keys: KeyBindings,
+    pub export_bibtex: KeyBindings,
+    pub tag_browser: KeyBindings,
+    pub collapse_tag: KeyBindings,
+    pub expand_tag: KeyBindings,
+    pub toggle_bibtex: KeyBindings,
+    pub switch_library: KeyBindings,
+    pub help: KeyBindings,
 }
 
 impl Default for KeybindsFromFile {
     fn default() -> Self {
         KeybindsFromFile {
-            quit: 'q',
-            next: 'j',
-            previous: 'k',
-            bibtex_to_clipboard: 'b',
-            edit: 'e',
-            delete: 'd',
-            open_in_pdfviewer: 'o',
+            quit: KeyBindings::from_specs(&["q"]),
+            next: KeyBindings::from_specs(&["j", "<Down>"]),
+            previous: KeyBindings::from_specs(&["k", "<Up>"]),
+            page_down: KeyBindings::from_specs(&["<C-f>", "<PageDown>", "J"]),
+            page_up: KeyBindings::from_specs(&["<C-b>", "<PageUp>", "K"]),
+            half_page_down: KeyBindings::from_specs(&["<C-d>", "D"]),
+            half_page_up: KeyBindings::from_specs(&["<C-u>", "U"]),
+            go_to_top: KeyBindings::from_specs(&["gg", "<Home>"]),
+            go_to_bottom: KeyBindings::from_specs(&["G", "<End>"]),
+            go_to_index: KeyBindings::from_specs(&[":"]),
+            bibtex_to_clipboard: KeyBindings::from_specs(&["b"]),
+            edit: KeyBindings::from_specs(&["e"]),
+            edit_field: KeyBindings::from_specs(&["i"]),
+            edit_tags: KeyBindings::from_specs(&["t"]),
+            delete: KeyBindings::from_specs(&["d"]),
+            undo_delete: KeyBindings::from_specs(&["u"]),
+            open_in_pdfviewer: KeyBindings::from_specs(&["o"]),
+            toggle_mark: KeyBindings::from_specs(&["<Space>"]),
+            visual_mark: KeyBindings::from_specs(&["V"]),
+            clear_marks: KeyBindings::from_specs(&["C"]),
+            set_status: KeyBindings::from_specs(&["s"]),
+            copy_citation_keys: KeyBindings::from_specs(&["c"]),
+            export_bibtex: KeyBindings::from_specs(&["x"]),
+            tag_browser: KeyBindings::from_specs(&["T"]),
+            collapse_tag: KeyBindings::from_specs(&["h", "<Left>"]),
+            expand_tag: KeyBindings::from_specs(&["l", "<Right>"]),
+            toggle_bibtex: KeyBindings::from_specs(&["B"]),
+            switch_library: KeyBindings::from_specs(&["L"]),
+            help: KeyBindings::from_specs(&["?"]),
+        }
+    }
+}
+
+/// The blocks that can be shown inside the content side of the TUI.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentPane {
+    Title,
+    Authors,
+    Description,
+    Bibtex,
+}
+
+/// The ways the names of the authors can be shown in the author block of the TUI, e.g. for
+/// Johannes Diderik van der Waals: `Full` gives "Johannes Diderik van der Waals", `Initials`
+/// "J. D. van der Waals", `FamilyGiven` "van der Waals, Johannes Diderik" and `Family`
+/// "van der Waals".
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorFormat {
+    Full,
+    Initials,
+    FamilyGiven,
+    Family,
+}
+
+/// Summary of the [layout] section of the configuration file.
+///
+/// Any field not specified by the configuration takes a default as
+/// specified by the Default implementation. Sizes are percentages: the
+/// `explorer_width` of the terminal width, the `tags_height` of the
+/// explorer, and the heights of the content panes relative to each other.
+/// The `content_panes` are shown from top to bottom in the given order,
+/// panes that are left out are hidden. When the terminal is narrower than
+/// `stack_below_width` columns, the explorer is put above the content, taking
+/// `stacked_explorer_height` of the terminal height. A `stack_below_width`
+/// of 0 never stacks. The `author_format` decides how the names of the
+/// authors are shown.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutFromFile {
+    pub explorer_width: u16,
+    pub tags_height: u16,
+    pub show_tags: bool,
+    pub content_panes: Vec<ContentPane>,
+    pub title_height: u16,
+    pub authors_height: u16,
+    pub description_height: u16,
+    pub bibtex_height: u16,
+    pub stack_below_width: u16,
+    pub stacked_explorer_height: u16,
+    pub author_format: AuthorFormat,
+}
+
+impl Default for LayoutFromFile {
+    fn default() -> Self {
+        LayoutFromFile {
+            explorer_width: 30,
+            tags_height: 15,
+            show_tags: true,
+            content_panes: vec![
+                ContentPane::Title,
+                ContentPane::Authors,
+                ContentPane::Description,
+            ],
+            title_height: 20,
+            authors_height: 20,
+            description_height: 60,
+            bibtex_height: 40,
+            stack_below_width: 0,
+            stacked_explorer_height: 40,
+            author_format: AuthorFormat::Full,
         }
     }
 }
 
+/// Summary of the [tags] section of the configuration file.
+///
+/// Any field not specified by the configuration takes a default as
+/// specified by the Default implementation. The normalisation options decide
+/// which tags are seen as the same tag when filtering, listing and adding tags:
+/// `ignore_case` ignores upper and lower case, `ignore_accents` ignores accents
+/// on letters, e.g. `é` is seen as `e`, and `trim_whitespace` ignores whitespace
+/// around each level of a tag and repeated whitespace inside it. The `aliases`
+/// map variants of a tag onto the canonical tag, e.g. `QFT = "physics/qft"`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagsFromFile {
+    pub ignore_case: bool,
+    pub ignore_accents: bool,
+    pub trim_whitespace: bool,
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl Default for TagsFromFile {
+    fn default() -> Self {
+        TagsFromFile {
+            ignore_case: false,
+            ignore_accents: false,
+            trim_whitespace: true,
+            aliases: BTreeMap::new(),
+        }
+    }
+}
+
+/// Summary of a library in the [libraries] section of the configuration file,
+/// e.g. `[libraries.shared]`.
+///
+/// The `path` field is the directory of the paper files of the library, and the
+/// `pdf_dir` the directory of its PDF files. If `pdf_dir` is empty, the `pdf_dir`
+/// of the [general] section is used. Both may start with `~` or `$HOME`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LibraryFromFile {
+    pub path: String,
+    pub pdf_dir: String,
+}
+
 /// Representation of the configuration Toml file as a Rust struct.
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(default)]
@@ -204,6 +505,9 @@ pub struct ConfigFromFile {
     pub general: GeneralFromFile,
     pub colors: ColorsFromFile,
     pub keybinds: KeybindsFromFile,
+    pub layout: LayoutFromFile,
+    pub tags: TagsFromFile,
+    pub libraries: BTreeMap<String, LibraryFromFile>,
 }
 
 impl Default for ConfigFromFile {
@@ -212,46 +516,85 @@ impl Default for ConfigFromFile {
             general: GeneralFromFile::default(),
             colors: ColorsFromFile::default(),
             keybinds: KeybindsFromFile::default(),
+            layout: LayoutFromFile::default(),
+            tags: TagsFromFile::default(),
+            libraries: BTreeMap::new(),
         }
     }
 }
 
 /// Identical to `ConfigFromFile`, but with `ColorsFromFile` replaced
-/// by the `TuiColors` which the program needs for rendering.
+/// by the `TuiColors` which the program needs for rendering. The
+/// `diagnostics` are the problems found in the configuration file, the
+/// settings they are about fall back to their defaults.
 #[derive(Debug, Clone)]
 pub struct Config {
     pub general: GeneralFromFile,
     pub colors: TuiColors,
     pub keybinds: KeybindsFromFile,
+    pub layout: LayoutFromFile,
+    pub tags: TagsFromFile,
+    pub libraries: BTreeMap<String, LibraryFromFile>,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl Config {
     /// Given file path to configuration file, create `Config` struct.
+    /// Theme files are looked for in the `themes` directory next to it.
     pub fn from_config_file(filepath: &std::path::PathBuf) -> Self {
-        let config_from_file = parse_config_file(filepath);
+        let (config_from_file, colors, diagnostics) = parse_config_file(filepath);
         let config = Config {
             general: config_from_file.general,
-            colors: TuiColors::from_config_file_colors(&config_from_file.colors),
+            colors,
             keybinds: config_from_file.keybinds,
+            layout: config_from_file.layout,
+            tags: config_from_file.tags,
+            libraries: config_from_file.libraries,
+            diagnostics,
         };
         return config;
     }
 }
 
+/// Get the directory theme files are looked for in, the `themes` directory
+/// next to the configuration file at `filepath`.
+pub fn theme_dir(filepath: &std::path::PathBuf) -> std::path::PathBuf {
+    let mut theme_dir = match filepath.parent() {
+        Some(p) => p.to_path_buf(),
+        None => std::path::PathBuf::new(),
+    };
+    theme_dir.push("themes");
+    return theme_dir;
+}
+
 /// Given file path to configuration file, parse the file contents
-/// into the `ConfigFromFile` Rust struct.
-pub fn parse_config_file(filepath: &std::path::PathBuf) -> ConfigFromFile {
+/// into the `ConfigFromFile` Rust struct and the `TuiColors` it sets,
+/// together with the problems found in it, see `validate`. Each problem
+/// is logged as a warning.
+pub fn parse_config_file(
+    filepath: &std::path::PathBuf,
+) -> (ConfigFromFile, TuiColors, Vec<Diagnostic>) {
     // Check if the config file exists
     if !filepath.exists() {
-        return ConfigFromFile::default();
+        return (ConfigFromFile::default(), TuiColors::default(), Vec::new());
     }
 
     // Open the file
     let mut file = match std::fs::File::open(filepath) {
         Ok(v) => v,
         Err(err) => {
-            log::warn!("Unable to open {filepath:?}, default configuration is used: {err}");
-            return ConfigFromFile::default();
+            let diagnostic = Diagnostic {
+                position: None,
+                message: format!(
+                    "unable to open the file, the default configuration is used: {err}"
+                ),
+            };
+            log::warn!("{filepath:?}: {diagnostic}");
+            return (
+                ConfigFromFile::default(),
+                TuiColors::default(),
+                vec![diagnostic],
+            );
         }
     };
     // Read contents from the file
@@ -259,27 +602,24 @@ pub fn parse_config_file(filepath: &std::path::PathBuf) -> ConfigFromFile {
     match file.read_to_string(&mut contents) {
         Ok(_) => {}
         Err(err) => {
-            log::warn!(
-                "Unable to read contents of {filepath:?}, default configuration is used: {err}"
+            let diagnostic = Diagnostic {
+                position: None,
+                message: format!(
+                    "unable to read the file, the default configuration is used: {err}"
+                ),
+            };
+            log::warn!("{filepath:?}: {diagnostic}");
+            return (
+                ConfigFromFile::default(),
+                TuiColors::default(),
+                vec![diagnostic],
             );
-            return ConfigFromFile::default();
         }
     };
-    // Parse the file contents
-    let parsed_toml: toml::Value = match toml::from_str(&contents) {
-        Ok(v) => v,
-        Err(err) => {
-            log::warn!("Unable to parse contents of {filepath:?} into `toml::Value`, default configuration is used: {err}");
-            return ConfigFromFile::default();
-        }
-    };
-    // Convert config Toml to struct
-    let config_struct: ConfigFromFile = match parsed_toml.try_into() {
-        Ok(v) => v,
-        Err(err) => {
-            log::warn!("Unable to deserialize contents of {filepath:?} into `ConfigFromFile` struct, default configuration is used: {err}");
-            return ConfigFromFile::default();
-        }
-    };
-    return config_struct;
+    // Parse the file contents, key by key
+    let (config_struct, colors, diagnostics) = validate(&contents, &theme_dir(filepath));
+    for diagnostic in diagnostics.iter() {
+        log::warn!("{filepath:?}:{diagnostic}");
+    }
+    return (config_struct, colors, diagnostics);
 }
@@ -0,0 +1,426 @@
+use super::config::{
+    parse_config_file, ColorsFromFile, ConfigFromFile, GeneralFromFile, KeybindsFromFile,
+    LayoutFromFile, LibraryFromFile, TagsFromFile, TuiColors,
+};
+use serde::de::DeserializeOwned;
+
+/// Problem found in the configuration file.
+///
+/// The `position` field contains the line and column, both starting at 1, the
+/// problem was found at, or `None` if the problem is not tied to a place in the
+/// file, e.g. when the file could not be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some((line, column)) => return write!(f, "{}:{}: {}", line, column, self.message),
+            None => return write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Key spans of the configuration file, used to point the diagnostics at the
+/// place in the file they are about.
+struct SourceMap<'a> {
+    contents: &'a str,
+    document: Option<toml_edit::ImDocument<&'a str>>,
+}
+
+impl<'a> SourceMap<'a> {
+    fn new(contents: &'a str) -> Self {
+        SourceMap {
+            contents,
+            document: toml_edit::ImDocument::parse(contents).ok(),
+        }
+    }
+    /// Get the line and column of the key at `path`, or of its value if `value` is set.
+    fn position(&self, path: &[&str], value: bool) -> Option<(usize, usize)> {
+        let document = self.document.as_ref()?;
+        let mut table: &dyn toml_edit::TableLike = document.as_table();
+        let mut span = None;
+        for (i, key) in path.iter().enumerate() {
+            let (k, item) = table.get_key_value(key)?;
+            span = if value { item.span() } else { k.span() };
+            if i + 1 < path.len() {
+                table = item.as_table_like()?;
+            }
+        }
+        return span.map(|s| line_column(self.contents, s.start));
+    }
+}
+
+/// Convert the byte `offset` into `contents` to a line and column, both starting at 1.
+fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = match before.rfind('\n') {
+        Some(i) => i + 1,
+        None => 0,
+    };
+    let column = before[line_start..].chars().count() + 1;
+    return (line, column);
+}
+
+/// Parse the `contents` of a configuration file into `ConfigFromFile` and the `TuiColors`
+/// it sets, together with the problems found in it. Every key is checked on its own, so an
+/// unknown key or a value of the wrong type only makes that key fall back to its default.
+/// The invalid colors and themes that cannot be found, looked for in `theme_dir`, that
+/// `TuiColors::from_config_file_colors` reports are included as well. Only a file that is
+/// not valid Toml falls back to the default configuration as a whole.
+pub fn validate(
+    contents: &str,
+    theme_dir: &std::path::PathBuf,
+) -> (ConfigFromFile, TuiColors, Vec<Diagnostic>) {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut config = ConfigFromFile::default();
+    let table: toml::Table = match toml::from_str(contents) {
+        Ok(t) => t,
+        Err(err) => {
+            diagnostics.push(Diagnostic {
+                position: err.span().map(|s| line_column(contents, s.start)),
+                message: format!(
+                    "{}, the default configuration is used",
+                    err.message().trim_end().replace('\n', ", ")
+                ),
+            });
+            return (config, TuiColors::default(), diagnostics);
+        }
+    };
+    let source = SourceMap::new(contents);
+
+    for (section, value) in table.iter() {
+        let section_table = match value {
+            toml::Value::Table(t) => t,
+            _ => {
+                if is_section(section) {
+                    diagnostics.push(Diagnostic {
+                        position: source.position(&[section], true),
+                        message: format!("`{}` must be a table, like [{}]", section, section),
+                    });
+                } else {
+                    diagnostics.push(unknown_section(section, &source));
+                }
+                continue;
+            }
+        };
+        match section.as_str() {
+            "general" => {
+                config.general = merge_section::<GeneralFromFile>(
+                    &[section],
+                    section_table,
+                    &source,
+                    &mut diagnostics,
+                );
+            }
+            "colors" => {
+                config.colors = merge_section::<ColorsFromFile>(
+                    &[section],
+                    section_table,
+                    &source,
+                    &mut diagnostics,
+                );
+            }
+            "keybinds" => {
+                config.keybinds = merge_section::<KeybindsFromFile>(
+                    &[section],
+                    section_table,
+                    &source,
+                    &mut diagnostics,
+                );
+            }
+            "layout" => {
+                config.layout = merge_section::<LayoutFromFile>(
+                    &[section],
+                    section_table,
+                    &source,
+                    &mut diagnostics,
+                );
+            }
+            "tags" => {
+                config.tags = merge_section::<TagsFromFile>(
+                    &[section],
+                    section_table,
+                    &source,
+                    &mut diagnostics,
+                );
+            }
+            "libraries" => {
+                for (name, value) in section_table.iter() {
+                    let library_table = match value {
+                        toml::Value::Table(t) => t,
+                        _ => {
+                            diagnostics.push(Diagnostic {
+                                position: source.position(&[section, name], true),
+                                message: format!(
+                                    "library `{}` must be a table, like [libraries.{}]",
+                                    name, name
+                                ),
+                            });
+                            continue;
+                        }
+                    };
+                    let library = merge_section::<LibraryFromFile>(
+                        &[section, name],
+                        library_table,
+                        &source,
+                        &mut diagnostics,
+                    );
+                    if library.path.is_empty() {
+                        diagnostics.push(Diagnostic {
+                            position: source.position(&[section, name], false),
+                            message: format!("library `{}` has no `path`, it cannot be used", name),
+                        });
+                        continue;
+                    }
+                    config.libraries.insert(name.clone(), library);
+                }
+            }
+            _ => diagnostics.push(unknown_section(section, &source)),
+        }
+    }
+    // Convert the colors here, so the problems reported are the ones the TUI runs into.
+    let (colors, problems) = TuiColors::from_config_file_colors(&config.colors, theme_dir);
+    for problem in problems {
+        diagnostics.push(Diagnostic {
+            position: source.position(&["colors", &problem.key], true),
+            message: problem.message,
+        });
+    }
+    // Report the problems in the order they appear in the file.
+    diagnostics.sort_by_key(|d| d.position);
+    return (config, colors, diagnostics);
+}
+
+/// Check if `name` is one of the sections of the configuration file.
+fn is_section(name: &str) -> bool {
+    return [
+        "general",
+        "colors",
+        "keybinds",
+        "layout",
+        "tags",
+        "libraries",
+    ]
+    .contains(&name);
+}
+
+fn unknown_section(section: &str, source: &SourceMap) -> Diagnostic {
+    return Diagnostic {
+        position: source.position(&[section], false),
+        message: format!(
+            "unknown section `{}`, expected one of `general`, `colors`, `keybinds`, `layout`, `tags`, `libraries`",
+            section
+        ),
+    };
+}
+
+/// Deserialize the keys of `table`, found at `path` in the file, into `T`. Each key is
+/// deserialized on its own first, keys that fail are reported and left out, so they take
+/// the default of `T`.
+fn merge_section<T: DeserializeOwned + Default>(
+    path: &[&str],
+    table: &toml::Table,
+    source: &SourceMap,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> T {
+    let mut accepted = toml::Table::new();
+    for (key, value) in table.iter() {
+        let mut single = toml::Table::new();
+        single.insert(key.clone(), value.clone());
+        match T::deserialize(toml::Value::Table(single)) {
+            Ok(_) => {
+                accepted.insert(key.clone(), value.clone());
+            }
+            Err(err) => {
+                let message = err.message().trim_end().replace('\n', ", ");
+                let is_unknown = message.starts_with("unknown field");
+                let mut key_path = path.to_vec();
+                key_path.push(key);
+                diagnostics.push(Diagnostic {
+                    position: source.position(&key_path, !is_unknown),
+                    message: if is_unknown {
+                        message
+                    } else {
+                        format!(
+                            "invalid value for `{}`, the default is used: {}",
+                            key_path.join("."),
+                            message
+                        )
+                    },
+                });
+            }
+        }
+    }
+    match T::deserialize(toml::Value::Table(accepted)) {
+        Ok(t) => return t,
+        Err(err) => {
+            diagnostics.push(Diagnostic {
+                position: source.position(path, false),
+                message: format!(
+                    "`{}` could not be read, the defaults are used: {}",
+                    path.join("."),
+                    err.message()
+                ),
+            });
+            return T::default();
+        }
+    }
+}
+
+/// Parse the `contents` of a theme file into `ColorsFromFile`, together with the problems
+/// found in it. Like in `validate`, keys that fail are left out. An error is returned if
+/// the contents are not valid Toml.
+pub fn parse_theme(contents: &str) -> Result<(ColorsFromFile, Vec<Diagnostic>), Diagnostic> {
+    let table: toml::Table = match toml::from_str(contents) {
+        Ok(t) => t,
+        Err(err) => {
+            return Err(Diagnostic {
+                position: err.span().map(|s| line_column(contents, s.start)),
+                message: err.message().trim_end().replace('\n', ", "),
+            });
+        }
+    };
+    let source = SourceMap::new(contents);
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let theme = merge_section::<ColorsFromFile>(&[], &table, &source, &mut diagnostics);
+    return Ok((theme, diagnostics));
+}
+
+/// Check the configuration file at `filepath` and print every problem found in it to the
+/// terminal, prefixed by the file path and the position of the problem. The program exits
+/// with a non-zero exit code if there are any problems.
+pub fn check_config(filepath: &std::path::PathBuf) {
+    if !filepath.exists() {
+        println!(
+            "No configuration file found at {}, the default configuration is used.",
+            filepath.display()
+        );
+        return;
+    }
+    let (_, _, diagnostics) = parse_config_file(filepath);
+    if diagnostics.is_empty() {
+        println!("No problems found in {}.", filepath.display());
+        return;
+    }
+    for diagnostic in diagnostics.iter() {
+        match diagnostic.position {
+            Some(_) => println!("{}:{}", filepath.display(), diagnostic),
+            None => println!("{}: {}", filepath.display(), diagnostic),
+        }
+    }
+    println!("{} problem(s) found.", diagnostics.len());
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::binding::KeyBindings;
+    use ratatui::style::Color;
+
+    /// Validate `contents` without any theme files.
+    fn check(contents: &str) -> (ConfigFromFile, TuiColors, Vec<Diagnostic>) {
+        return validate(
+            contents,
+            &std::path::PathBuf::from("/nonexistent/dumpling/themes"),
+        );
+    }
+
+    #[test]
+    fn bad_key_next_to_good_keys() {
+        let contents = "[general]\nkey_sequence_timeout = 500\nstatus_timeout = \"soon\"\nlive_reload = false\n";
+        let (config, _, diagnostics) = check(contents);
+        assert_eq!(config.general.key_sequence_timeout, 500);
+        assert!(!config.general.live_reload);
+        assert_eq!(
+            config.general.status_timeout,
+            GeneralFromFile::default().status_timeout
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].position, Some((3, 18)));
+        assert!(diagnostics[0]
+            .message
+            .starts_with("invalid value for `general.status_timeout`"));
+    }
+
+    #[test]
+    fn unknown_keys_and_sections() {
+        let contents = "[layout]\nshow_tags = false\n  bogus = 1\n\n[nope]\nx = 1\n";
+        let (config, _, diagnostics) = check(contents);
+        assert!(!config.layout.show_tags);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].position, Some((3, 3)));
+        assert!(diagnostics[0].message.starts_with("unknown field `bogus`"));
+        assert_eq!(diagnostics[1].position, Some((5, 2)));
+        assert!(diagnostics[1].message.starts_with("unknown section `nope`"));
+    }
+
+    #[test]
+    fn invalid_key_binding_falls_back() {
+        let contents = "[keybinds]\nquit = \"<Nope>\"\nnext = [\"n\", \"<Down>\"]\n";
+        let (config, _, diagnostics) = check(contents);
+        assert_eq!(config.keybinds.quit, KeybindsFromFile::default().quit);
+        assert_eq!(
+            config.keybinds.next,
+            KeyBindings::from_specs(&["n", "<Down>"])
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].position, Some((2, 8)));
+    }
+
+    #[test]
+    fn invalid_colors_and_missing_theme() {
+        let contents =
+            "[colors]\ntheme = \"missing\"\npopup_text = \"#12345\"\ntag_content = \"#38b\"\n";
+        let (_, colors, diagnostics) = check(contents);
+        assert_eq!(colors.tag_content, Color::Rgb(0x33, 0x88, 0xbb));
+        assert_eq!(colors.popup_text, TuiColors::default().popup_text);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].position, Some((2, 9)));
+        assert!(diagnostics[0].message.contains("theme \"missing\""));
+        assert_eq!(diagnostics[1].position, Some((3, 14)));
+        assert!(diagnostics[1]
+            .message
+            .starts_with("invalid color \"#12345\" for `colors.popup_text`"));
+    }
+
+    #[test]
+    fn library_without_path() {
+        let contents =
+            "[libraries.work]\npath = \"~/work\"\n\n[libraries.home]\npdf_dir = \"~/pdf\"\n";
+        let (config, _, diagnostics) = check(contents);
+        assert!(config.libraries.contains_key("work"));
+        assert!(!config.libraries.contains_key("home"));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].position, Some((4, 12)));
+    }
+
+    #[test]
+    fn invalid_toml_uses_defaults() {
+        let (config, _, diagnostics) = check("[general]\nstatus_timeout = \n");
+        assert_eq!(
+            config.general.status_timeout,
+            GeneralFromFile::default().status_timeout
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].position.map(|p| p.0), Some(2));
+        assert!(diagnostics[0]
+            .message
+            .ends_with("the default configuration is used"));
+    }
+
+    #[test]
+    fn diagnostic_display() {
+        let diagnostic = Diagnostic {
+            position: Some((3, 18)),
+            message: String::from("problem"),
+        };
+        assert_eq!(diagnostic.to_string(), "3:18: problem");
+        assert_eq!(line_column("ab\ncdé f", 8), (2, 5));
+    }
+}
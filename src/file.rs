@@ -1,2 +1,7 @@
+pub mod library;
 pub mod loader;
+pub mod names;
+pub mod normalize;
 pub mod parser;
+pub mod trash;
+pub mod watcher;
@@ -0,0 +1,86 @@
+use super::loader::expand_filepath;
+use crate::args::parser::NAME;
+use crate::configuration::config::Config;
+
+/// Library of paper files the program works on.
+///
+/// The `name` field is the name of the library in the [libraries] section of the
+/// configuration file, or `None` if the library was given as a directory.
+/// The `folderdir` field is the directory of the paper files, and the `pdf_dir`
+/// the directory of the PDF files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Library {
+    pub name: Option<String>,
+    pub folderdir: std::path::PathBuf,
+    pub pdf_dir: String,
+}
+
+impl Library {
+    /// Get the library called `name` in the configuration, if there is one.
+    pub fn from_name(name: &str, config: &Config) -> Option<Self> {
+        let library = config.libraries.get(name)?;
+        let pdf_dir = if library.pdf_dir.is_empty() {
+            config.general.pdf_dir.clone()
+        } else {
+            library.pdf_dir.clone()
+        };
+        return Some(Library {
+            name: Some(name.to_string()),
+            folderdir: expand_filepath(&std::path::PathBuf::from(&library.path)),
+            pdf_dir,
+        });
+    }
+
+    /// Get the library described by `spec`, which is either the name of a library in the
+    /// configuration or a directory. A directory uses the `pdf_dir` of the [general] section.
+    pub fn from_spec(spec: &str, config: &Config) -> Self {
+        match Library::from_name(spec, config) {
+            Some(library) => return library,
+            None => {
+                return Library {
+                    name: None,
+                    folderdir: expand_filepath(&std::path::PathBuf::from(spec)),
+                    pdf_dir: config.general.pdf_dir.clone(),
+                };
+            }
+        }
+    }
+
+    /// Get the library to use when no library is asked for: the `default_library` of the
+    /// configuration if it is set, otherwise $HOME/.cache/dumpling/.
+    pub fn default_library(config: &Config) -> Self {
+        let name = &config.general.default_library;
+        if !name.is_empty() {
+            match Library::from_name(name, config) {
+                Some(library) => return library,
+                None => {
+                    log::warn!("Default library {name:?} is not in the [libraries] section of the configuration, $HOME/.cache/dumpling/ is used instead.");
+                }
+            }
+        }
+        let mut folderdir = match dirs::cache_dir() {
+            Some(p) => p,
+            None => {
+                log::error!("Could not obtain $HOME/.cache/ as `PathBuf`");
+                std::process::exit(1);
+            }
+        };
+        folderdir.push(NAME);
+        return Library {
+            name: None,
+            folderdir,
+            pdf_dir: config.general.pdf_dir.clone(),
+        };
+    }
+
+    /// Create the directory of the paper files if it does not exist yet.
+    pub fn create_dir(&self) -> Result<(), String> {
+        if self.folderdir.exists() {
+            return Ok(());
+        }
+        match std::fs::create_dir_all(&self.folderdir) {
+            Ok(_) => return Ok(()),
+            Err(err) => return Err(format!("Could not create {:?}: {}", self.folderdir, err)),
+        }
+    }
+}
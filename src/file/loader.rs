@@ -1,12 +1,22 @@
-use super::parser::{parse_paper_toml, Paper};
+use super::library::Library;
+use super::normalize::TagNormalizer;
+use super::parser::{overwrite_paper, parse_paper_toml, Paper, PaperField, Tag};
+use super::trash::{move_to_trash, restore_from_trash};
 use cli_clipboard::{ClipboardContext, ClipboardProvider};
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Outcome of an action performed on the papers, holding a message for the user
+/// describing either what was done or what went wrong.
+pub type ActionResult = Result<String, String>;
 
 /// In the given `filedir`, look for all the files of the valid *.toml format and
-/// store the path to them in a vector that is returned.
+/// store the path to them in a vector that is returned. If `tag_filters` is not
+/// empty, only the papers that have all of those tags, or a child of
+/// each of them, are included. Tags are compared with the `normalizer`.
 pub fn get_all_valid_filepaths(
     folderdir: &std::path::PathBuf,
-    tag_filter: &Option<String>,
+    tag_filters: &[String],
+    normalizer: &TagNormalizer,
 ) -> Vec<std::path::PathBuf> {
     // Initialise vector
     let mut all_file_paths: Vec<std::path::PathBuf> = Vec::new();
@@ -43,31 +53,29 @@ pub fn get_all_valid_filepaths(
             }
         };
         if re.is_match(&file_name) {
-            match tag_filter {
-                Some(tag) => {
-                    // Parse the paper
-                    let paper = match parse_paper_toml(&file_path) {
-                        Some(p) => p,
-                        None => {
-                            log::warn!("Contents of {file_path:?} could not be deserialised into `Paper` struct, continuing to next paper to check tag of");
-                            continue;
-                        }
-                    };
-                    // Check if any of the tags matches the given tag
-                    for tag_paper in &paper.tags {
-                        if *tag == tag_paper.label {
-                            // If the tag is present, include it in the valid paths
-                            all_file_paths.push(file_path);
-                            break;
-                        }
-                    }
-                }
+            if tag_filters.is_empty() {
+                all_file_paths.push(file_path);
+                continue;
+            }
+            // Parse the paper
+            let paper = match parse_paper_toml(&file_path) {
+                Some(p) => p,
                 None => {
-                    all_file_paths.push(file_path);
+                    log::warn!("Contents of {file_path:?} could not be deserialised into `Paper` struct, continuing to next paper to check tag of");
+                    continue;
                 }
+            };
+            // Include the paper only if every filter tag, or a child of it, is present
+            let has_all_tags = tag_filters
+                .iter()
+                .all(|tag| paper.tags.iter().any(|t| normalizer.matches(&t.label, tag)));
+            if has_all_tags {
+                all_file_paths.push(file_path);
             }
         }
     }
+    // Sort the paths, so the order is the same every time the directory is read.
+    all_file_paths.sort();
     return all_file_paths;
 }
 
@@ -79,169 +87,205 @@ pub fn get_all_valid_filepaths(
 /// paths.
 /// The `paper` field contains the parsed `Paper` structs of the loaded paths specified by
 /// `loaded_paths`.
+/// The `library_name` field contains the configured name of the library the papers belong to, if
+/// it has one.
+/// The `folderdir` field contains the directory the paper files were loaded from.
+/// The `pdf_dir` field contains the directory the PDF files of the library are stored in.
+/// The `tag_normalizer` field decides which tags are the same when filtering and editing tags.
+/// The `tag_filters` field contains the tags the `valid_paths` were selected with, papers need
+/// all of them to be valid.
+/// The `load_size` field contains the `load` parameter the `Loader` was created with.
+/// The `marked` field contains the paths of the papers marked for bulk actions.
+/// The `last_removed` field contains the paths in the trash of the papers removed by the last
+/// call to `Loader::remove_files`, so the removal can be undone.
 #[derive(Clone, Debug)]
 pub struct Loader {
+    pub library_name: Option<String>,
+    pub folderdir: std::path::PathBuf,
+    pub pdf_dir: String,
+    pub tag_normalizer: TagNormalizer,
+    pub tag_filters: Vec<String>,
     pub valid_paths: Vec<std::path::PathBuf>,
     pub loaded_paths: VecDeque<usize>,
     pub papers: VecDeque<Paper>,
+    pub load_size: i32,
+    pub marked: HashSet<std::path::PathBuf>,
+    pub last_removed: Vec<std::path::PathBuf>,
 }
 
 impl Loader {
-    /// Given a load size and the directory where the paper information
-    /// files are stored, create a new instance of `Loader`. The
+    /// Given a load size and the library whose paper information
+    /// files are loaded, create a new instance of `Loader`. The
     /// `loaded_paths` will be as large as possible, bounded by the
-    /// `load` parameter.
-    pub fn load(load: i32, folderdir: &std::path::PathBuf, tag_filter: &Option<String>) -> Self {
+    /// `load` parameter. Tags are compared with the `tag_normalizer`.
+    pub fn load(
+        load: i32,
+        library: &Library,
+        tag_filter: &Option<String>,
+        tag_normalizer: &TagNormalizer,
+    ) -> Self {
         // Initialise the `Loader` fields.
-        let valid_paths = get_all_valid_filepaths(folderdir, tag_filter);
-        let mut loaded_paths: VecDeque<usize> = VecDeque::new();
-        let mut papers: VecDeque<Paper> = VecDeque::new();
-
-        for i in 0..=load {
-            let file_path = match valid_paths.get(i as usize) {
+        let tag_filters: Vec<String> = tag_filter.iter().cloned().collect();
+        let valid_paths = get_all_valid_filepaths(&library.folderdir, &tag_filters, tag_normalizer);
+        let mut loader = Loader {
+            library_name: library.name.clone(),
+            folderdir: library.folderdir.clone(),
+            pdf_dir: library.pdf_dir.clone(),
+            tag_normalizer: tag_normalizer.clone(),
+            tag_filters,
+            valid_paths,
+            loaded_paths: VecDeque::new(),
+            papers: VecDeque::new(),
+            load_size: load,
+            marked: HashSet::new(),
+            last_removed: Vec::new(),
+        };
+        loader.load_window(0);
+        return loader;
+    }
+    /// Amount of papers the loaded window holds when it is full, as determined by the
+    /// `load_size`.
+    pub fn window_len(&self) -> usize {
+        return self.load_size.max(0) as usize + 1;
+    }
+    /// Change the `load_size`, e.g. after the terminal was resized, and load a window of the
+    /// new size. The window keeps its start if possible, but moves so the paper pointed at by
+    /// `file_pointer` stays inside it. The new file pointer is returned.
+    pub fn set_load_size(&mut self, load: i32, file_pointer: usize) -> usize {
+        let selected_idx = self.path_index(file_pointer).unwrap_or(0);
+        let window_start = self.loaded_paths.front().cloned().unwrap_or(0);
+        self.load_size = load;
+        let window_len = self.window_len();
+        // Keep the window filled if possible, and move it so the selected paper is inside it.
+        let mut start = window_start.min(self.valid_paths.len().saturating_sub(window_len));
+        if selected_idx < start {
+            start = selected_idx;
+        } else if selected_idx >= start + window_len {
+            start = selected_idx + 1 - window_len;
+        }
+        self.load_window(start);
+        return self
+            .loaded_paths
+            .iter()
+            .position(|&l| l == selected_idx)
+            .unwrap_or(0);
+    }
+    /// Load the window of papers in `valid_paths` starting from the `start` index, so the
+    /// `Loader` acts as a virtual list over all the valid paths. The amount of loaded papers is
+    /// bounded by the `load_size`. Papers that were already loaded and are still inside the new
+    /// window are kept, so only the papers that scroll into view are read from disk.
+    pub fn load_window(&mut self, start: usize) {
+        let mut previous_papers: HashMap<usize, Paper> = self
+            .loaded_paths
+            .drain(..)
+            .zip(self.papers.drain(..))
+            .collect();
+        for i in start..(start + self.window_len()) {
+            let file_path = match self.valid_paths.get(i) {
                 Some(p) => p,
                 None => {
-                    // If we get here, the load is probably larger than the amount of valid paths.
-                    // We could break out of the loop here, but just to be safe we'll continue on
-                    // to the next iteration.
-                    continue;
+                    // If we get here, we are past the last valid path.
+                    break;
                 }
             };
-            let paper = match parse_paper_toml(file_path) {
+            let paper = match previous_papers.remove(&i) {
                 Some(p) => p,
-                None => {
-                    log::warn!("Contents of {file_path:?} could not be deserialised into `Paper` struct, continuing to load next paper");
-                    continue;
-                }
+                None => match parse_paper_toml(file_path) {
+                    Some(p) => p,
+                    None => {
+                        log::warn!("Contents of {file_path:?} could not be deserialised into `Paper` struct, continuing to load next paper");
+                        continue;
+                    }
+                },
             };
-            loaded_paths.push_back(i as usize);
-            papers.push_back(paper);
+            self.loaded_paths.push_back(i);
+            self.papers.push_back(paper);
         }
-
-        return Loader {
-            valid_paths,
-            loaded_paths,
-            papers,
-        };
     }
-    /// Load the next paper, if there is another valid paper to load.
-    pub fn load_next(&mut self, file_pointer: usize) -> usize {
-        let mut fp: usize = file_pointer;
+    /// Unload all the loaded papers and load the window starting from `start` again, reading
+    /// every paper from disk. This is needed when the paper files may have changed.
+    pub fn reload_window_from_disk(&mut self, start: usize) {
+        self.loaded_paths.clear();
+        self.papers.clear();
+        self.load_window(start);
+    }
+    /// Select the paper at index `path_idx` of `valid_paths`, clamped to the valid paths. If the
+    /// paper is not loaded, the window is moved as little as possible so it is. When that paper
+    /// could not be loaded, the search goes on past it, towards the start of `valid_paths` when
+    /// `backwards` is set and towards the end otherwise, until a paper loads. If there is none,
+    /// the closest paper in the other direction is selected. The file pointer to the selected
+    /// paper is returned.
+    pub fn select(&mut self, path_idx: usize, backwards: bool) -> usize {
         // Check if there is even anything to load, if not, return a file pointer of zero.
         if self.valid_paths.is_empty() {
             return 0;
         }
-        // Get the last loaded index
-        let last_load: usize = match self.loaded_paths.back() {
-            Some(v) => *v,
-            None => {
-                // There should be at least one element, the empty case was
-                // handled before. So we should never get here. In case we do,
-                // just return zero.
-                log::warn!("Attempting to load non-existent back of `VecDeque`.");
-                return 0;
+        let requested_idx = path_idx.min(self.valid_paths.len() - 1);
+        let mut path_idx = requested_idx;
+        let mut backwards = backwards;
+        let mut turned = false;
+        loop {
+            let first_load = self.loaded_paths.front().cloned().unwrap_or(0);
+            let last_load = self.loaded_paths.back().cloned().unwrap_or(0);
+            if self.loaded_paths.is_empty() || path_idx < first_load {
+                // Scroll up, so the selected paper is at the top of the window.
+                self.load_window(path_idx);
+            } else if path_idx > last_load {
+                // Scroll down, so the selected paper is at the bottom of the window.
+                self.load_window((path_idx + 1).saturating_sub(self.window_len()));
             }
-        };
-        // Get the next load path if possible.
-        let new_load_path = match self.valid_paths.get(last_load + 1) {
-            Some(p) => p,
-            None => {
-                // If we were unable to load the next path, we are out of bounds meaning there is
-                // nothing further to load and the file pointer needs to be updated.
-                if file_pointer >= self.papers.len() - 1 {
-                    fp = self.papers.len() - 1;
-                } else {
-                    fp += 1;
-                }
-                return fp;
+            let after = self.loaded_paths.iter().position(|&l| l >= path_idx);
+            let before = self.loaded_paths.iter().rposition(|&l| l <= path_idx);
+            let found = if backwards { before } else { after };
+            if let Some(file_pointer) = found {
+                return file_pointer;
             }
-        };
-        // Load the paper
-        let paper = match parse_paper_toml(new_load_path) {
-            Some(p) => p,
-            None => {
-                // If the program is being used correctly, we should never get here.
-                log::error!("Error loading the next `Paper`.");
-                std::process::exit(1);
+            // Skip over papers that could not be loaded, as long as there are paths left.
+            if backwards && path_idx > 0 {
+                path_idx -= 1;
+                continue;
             }
-        };
-        // Keep the load size by unloading the first elements and
-        // adding the new elements
-        self.loaded_paths.pop_front();
-        self.papers.pop_front();
-        self.loaded_paths.push_back(last_load + 1);
-        self.papers.push_back(paper);
-        // If we've gotten here, a new file has been loaded and the file pointer should remain as
-        // is.
-        return fp;
-    }
-    /// Load the previous paper, if there is a previous paper to load.
-    pub fn load_previous(&mut self, file_pointer: usize) -> usize {
-        let mut fp = file_pointer;
-        // Check if there is even anything to load, if not, return a file pointer of zero.
-        if self.valid_paths.is_empty() {
-            return 0;
-        }
-        // Get the first loaded index
-        let first_load: usize = match self.loaded_paths.front() {
-            Some(v) => *v,
-            None => {
-                // There should be at least one element, the empty case was
-                // handled before. So we should never get here. In case we do,
-                // just return zero.
-                log::warn!("Attempting to load non-existent front of `VecDeque`.");
-                return 0;
+            if !backwards && path_idx + 1 < self.valid_paths.len() {
+                path_idx += 1;
+                continue;
             }
-        };
-        // If first_load is zero, then subtracting can cause an overflow. Which needs to be handled
-        // before trying to get first_load - 1. This also means we are out of bounds for the
-        // valid_paths array and hence there is nothing more previous to load. The file pointer
-        // should be updated in this case.
-        if first_load <= 0 {
-            if file_pointer <= 0 {
-                fp = 0;
-            } else {
-                fp -= 1;
-            }
-            return fp;
-        }
-
-        let new_load_path = match self.valid_paths.get(first_load - 1) {
-            Some(p) => p,
-            None => {
-                // We should never get here because the usize overflow check above already handles
-                // it.
-                log::error!(
-                    "Attempting to access outside the bounds of the `Loader.valid_paths` array."
-                );
-                std::process::exit(1);
+            if let Some(file_pointer) = before.or(after) {
+                return file_pointer;
             }
-        };
-        // Load the new paper
-        let paper = match parse_paper_toml(new_load_path) {
-            Some(p) => p,
-            None => {
-                // If the program is being used correctly, we should never get here.
-                log::error!("Error loading the previous `Paper`.");
-                std::process::exit(1);
+            // No paper could be loaded in this direction, so search the other direction.
+            if turned {
+                return 0;
             }
+            turned = true;
+            backwards = !backwards;
+            path_idx = requested_idx;
+        }
+    }
+    /// Move the selection `delta` papers down, or up for a negative `delta`, starting from the
+    /// paper pointed at by `file_pointer`. The selection stops at the first and last paper. The new
+    /// file pointer is returned.
+    pub fn move_by(&mut self, file_pointer: usize, delta: isize) -> usize {
+        let current = match self.path_index(file_pointer) {
+            Some(i) => i,
+            None => return self.select(0, false),
         };
-        // Keep the load size by unloading the last entries and adding
-        // the new elements to the front.
-        self.loaded_paths.pop_back();
-        self.papers.pop_back();
-        self.loaded_paths.push_front(first_load - 1);
-        self.papers.push_front(paper);
-        // If we've gotten here, a new file has been loaded and the file pointer should remain as
-        // is.
-        return fp;
+        let target = current.saturating_add_signed(delta);
+        return self.select(target, delta < 0);
+    }
+    /// Load the next paper, if there is another valid paper to load.
+    pub fn load_next(&mut self, file_pointer: usize) -> usize {
+        return self.move_by(file_pointer, 1);
+    }
+    /// Load the previous paper, if there is a previous paper to load.
+    pub fn load_previous(&mut self, file_pointer: usize) -> usize {
+        return self.move_by(file_pointer, -1);
     }
 
     /// Given an index in the `Loader.papers` vector, copy the
     /// contents of the `Paper.bibtex` field to the system clipboard.
     /// The method uses the `cli-clipboard` crate, which should make
     /// this function on Linux (Wayland and X11), MacOS and Windows.
-    pub fn bibtex_entry_to_clipboard(&self, selected_idx: usize) {
+    pub fn bibtex_entry_to_clipboard(&self, selected_idx: usize) -> ActionResult {
         // Get the entry
         let bibtex_entry = match self.papers.get(selected_idx) {
             Some(p) => p.bibtex.clone(),
@@ -249,7 +293,7 @@ impl Loader {
                 log::warn!(
                     "Currently selected paper does not exist in the `Loader.papers` `VecDeque`. Stop copying bibtex contents to clipboard."
                 );
-                return;
+                return Err(String::from("No paper selected"));
             }
         };
         // Get clipboard context
@@ -257,24 +301,15 @@ impl Loader {
             Ok(c) => c,
             Err(err) => {
                 log::warn!("Error obtaining clipboard context: {err}");
-                return;
+                return Err(format!("Clipboard unavailable: {err}"));
             }
         };
         // Set contents
         match ctx.set_contents(bibtex_entry) {
-            Ok(_) => {}
+            Ok(_) => return Ok(String::from("Bibtex copied")),
             Err(err) => {
                 log::warn!("Error setting clipboard contents: {err}");
-            }
-        }
-        // Send notification
-        match std::process::Command::new("notify-send")
-            .arg("Bibtex copied")
-            .status()
-        {
-            Ok(_) => {}
-            Err(err) => {
-                log::warn!("Error sending notification with `notify-send`: {err}");
+                return Err(format!("Error copying bibtex: {err}"));
             }
         }
     }
@@ -283,13 +318,13 @@ impl Loader {
     /// open the selected file in Neovim. Another example, `command = "code"` will open the
     /// selected file in VS Code. This is of course assuming that the respective programs are
     /// installed on your system
-    pub fn open_file_in_editor(&self, selected_idx: usize, command: &String) {
+    pub fn open_file_in_editor(&self, selected_idx: usize, command: &String) -> ActionResult {
         // Get the file path pointer
         let fp_pointer = match self.loaded_paths.get(selected_idx) {
             Some(i) => *i,
             None => {
                 log::warn!("Currently selected paper does not exists in the `Loader.loaded_paths` `VecDeque`. Stop opening editor.");
-                return;
+                return Err(String::from("No paper selected"));
             }
         };
         // Get the file path
@@ -297,7 +332,7 @@ impl Loader {
             Some(p) => p.clone(),
             None => {
                 log::warn!("File pointer does not point to an existing element of the `Loader.valid_paths` vector. Stop opening editor.");
-                return;
+                return Err(String::from("No paper selected"));
             }
         };
         // Separate the command into parts by separating by whitespace
@@ -308,20 +343,20 @@ impl Loader {
                 log::warn!(
                     "The `Config.general.editor_command` should not be empty. Stop opening editor"
                 );
-                return;
+                return Err(String::from("The editor_command is empty"));
             }
         };
         let command_args: Vec<&str> = command_parts.collect();
         // Run the command
         match std::process::Command::new(program)
             .args(&command_args)
-            .arg(file_path)
+            .arg(&file_path)
             .spawn()
         {
-            Ok(_) => {}
+            Ok(_) => return Ok(format!("Opened {:?} in the editor", file_path)),
             Err(err) => {
                 log::warn!("Error executing command to open editor, check that your setting for `Config.general.editor_command` does what you think it does: {err}");
-                return;
+                return Err(format!("Error running `{}`: {err}", program));
             }
         }
     }
@@ -334,13 +369,13 @@ impl Loader {
         selected_idx: usize,
         pdf_viewer: &String,
         pdf_dir: &String,
-    ) {
+    ) -> ActionResult {
         // Get the document name of the currently selected paper.
         let file_name = match self.papers.get(selected_idx) {
             Some(p) => p.docname.clone(),
             None => {
                 log::warn!("Currently selected paper does not exist in the `Loader.papers` `VecDeque`. Stop opening PDF viewer.");
-                return;
+                return Err(String::from("No paper selected"));
             }
         };
         // Create path to that file using the given `pdf_dir`
@@ -350,89 +385,371 @@ impl Loader {
         file_path = expand_filepath(&file_path);
         // Check that the file exists, then open it in the provided
         // `pdf_viewer`.
-        if file_path.exists() {
-            match std::process::Command::new(pdf_viewer)
-                .arg(file_path)
-                .spawn()
-            {
-                Ok(_) => {
-                    return;
-                }
-                Err(err) => {
-                    log::warn!("Error executing command to open PDF viewer: {err}");
-                    return;
-                }
+        if !file_path.exists() {
+            log::warn!("PDF file {file_path:?} does not exist. Stop opening PDF viewer.");
+            return Err(format!("PDF {:?} not found", file_path));
+        }
+        match std::process::Command::new(pdf_viewer)
+            .arg(&file_path)
+            .spawn()
+        {
+            Ok(_) => return Ok(format!("Opened {:?}", file_path)),
+            Err(err) => {
+                log::warn!("Error executing command to open PDF viewer: {err}");
+                return Err(format!("Error running `{}`: {err}", pdf_viewer));
             }
         }
     }
-    /// Remove the currently selected file, and remove it from the
-    /// loader
-    ///
-    /// Note: Currently it is not removed from the `valid_paths` field,
-    /// this can cause some weird behaviour in some instances. We
-    /// will add this later, however, this requires some further
-    /// attention as changes to the `loaded_paths` field will also need
-    /// to be changed.
-    pub fn remove_file(&mut self, selected_idx: usize) {
-        // Get the file path pointer
-        let fp_pointer = match self.loaded_paths.get(selected_idx) {
-            Some(i) => *i,
+    /// Get the index in `valid_paths` of the loaded paper pointed at by `selected_idx`.
+    pub fn path_index(&self, selected_idx: usize) -> Option<usize> {
+        return self.loaded_paths.get(selected_idx).cloned();
+    }
+    /// Apply `modify` to the paper at index `path_idx` of `valid_paths`. The change is written
+    /// back to the paper Toml file and, if the paper is loaded, the loaded `Paper` is updated so
+    /// the TUI reflects the change immediately. If `modify` returns `false`, or anything else
+    /// fails, the paper is left untouched and `false` is returned.
+    pub fn modify_paper<F>(&mut self, path_idx: usize, modify: F) -> bool
+    where
+        F: FnOnce(&mut Paper) -> bool,
+    {
+        // Get the file path
+        let file_path = match self.valid_paths.get(path_idx) {
+            Some(p) => p.clone(),
             None => {
-                log::warn!("Currently selected paper does not exist in the `Loader.loaded_paths` `VecDeque`. Stop removing file.");
-                return;
+                log::warn!("File pointer does not point to an existing element of the `Loader.valid_paths` vector. Stop modifying paper.");
+                return false;
             }
         };
-        // Get the file path
-        let file_path = match self.valid_paths.get(fp_pointer) {
+        // Modify a copy of the paper, so nothing changes if any of the steps fail. Use the loaded
+        // paper if there is one, otherwise read it from the file.
+        let loaded_idx = self.loaded_paths.iter().position(|&i| i == path_idx);
+        let mut paper = match loaded_idx.and_then(|i| self.papers.get(i)) {
             Some(p) => p.clone(),
+            None => match parse_paper_toml(&file_path) {
+                Some(p) => p,
+                None => {
+                    log::warn!("Contents of {file_path:?} could not be deserialised into `Paper` struct. Stop modifying paper.");
+                    return false;
+                }
+            },
+        };
+        let original = paper.clone();
+        if !modify(&mut paper) {
+            return false;
+        }
+        if !overwrite_paper(&original, &paper, &file_path) {
+            return false;
+        }
+        if let Some(i) = loaded_idx {
+            self.papers[i] = paper;
+        }
+        return true;
+    }
+    /// Set the given `field` of the paper pointed at by `selected_idx` to `value`, see
+    /// `Loader::modify_paper`.
+    pub fn edit_field(&mut self, selected_idx: usize, field: PaperField, value: &str) -> bool {
+        let path_idx = match self.path_index(selected_idx) {
+            Some(i) => i,
             None => {
-                log::warn!("File pointer does not point to an existing element of the `Loader.valid_paths` vector. Stop removing file.");
-                return;
+                log::warn!("Currently selected paper does not exist in the `Loader.loaded_paths` `VecDeque`. Stop editing field.");
+                return false;
             }
         };
-        // Check if the file exists
-        if file_path.exists() {
-            // Delete the file
-            match std::fs::remove_file(file_path) {
-                Ok(_) => {}
-                Err(err) => {
-                    log::warn!("Error attempting to remove file. Stop removing file: {err}");
-                    return;
+        return self.edit_field_at(path_idx, field, value);
+    }
+    /// Set the given `field` of the paper at index `path_idx` of `valid_paths` to `value`, see
+    /// `Loader::modify_paper`.
+    pub fn edit_field_at(&mut self, path_idx: usize, field: PaperField, value: &str) -> bool {
+        return self.modify_paper(path_idx, |paper| field.set(paper, value));
+    }
+    /// Add `label` to the tags of the paper at index `path_idx` of `valid_paths`. Nothing happens
+    /// if the paper already has that tag, according to the `tag_normalizer`.
+    pub fn add_tag(&mut self, path_idx: usize, label: &str) -> bool {
+        let normalizer = self.tag_normalizer.clone();
+        return self.modify_paper(path_idx, |paper| {
+            if paper.tags.iter().any(|t| normalizer.same(&t.label, label)) {
+                return false;
+            }
+            paper.tags.push(Tag {
+                label: label.to_string(),
+            });
+            return true;
+        });
+    }
+    /// Remove `label` from the tags of the paper at index `path_idx` of `valid_paths`. Nothing
+    /// happens if the paper does not have that tag. Every spelling of the tag is removed,
+    /// according to the `tag_normalizer`.
+    pub fn remove_tag(&mut self, path_idx: usize, label: &str) -> bool {
+        let normalizer = self.tag_normalizer.clone();
+        return self.modify_paper(path_idx, |paper| {
+            let tag_count = paper.tags.len();
+            paper.tags.retain(|t| !normalizer.same(&t.label, label));
+            return paper.tags.len() != tag_count;
+        });
+    }
+    /// Move the paper files at the indices `path_idxs` of `valid_paths` to the trash, and remove
+    /// them from the loader. The loaded window is reloaded afterwards, keeping the paper pointed
+    /// at by `file_pointer` selected if it still exists. The new file pointer is returned. The
+    /// removal can be undone with `Loader::undo_remove`.
+    pub fn remove_files(&mut self, path_idxs: &[usize], file_pointer: usize) -> usize {
+        // Remember which paper was selected and where the loaded window started.
+        let selected_path = self
+            .path_index(file_pointer)
+            .and_then(|i| self.valid_paths.get(i).cloned());
+        let selected_idx = self.path_index(file_pointer).unwrap_or(0);
+        let window_start = self.loaded_paths.front().cloned().unwrap_or(0);
+        // Remove from the back, so the remaining indices stay valid.
+        let mut path_idxs: Vec<usize> = path_idxs.to_vec();
+        path_idxs.sort();
+        path_idxs.dedup();
+        self.last_removed.clear();
+        let mut removed_idxs: Vec<usize> = Vec::new();
+        for &path_idx in path_idxs.iter().rev() {
+            // Get the file path
+            let file_path = match self.valid_paths.get(path_idx) {
+                Some(p) => p.clone(),
+                None => {
+                    log::warn!("File pointer does not point to an existing element of the `Loader.valid_paths` vector. Skip removing file.");
+                    continue;
+                }
+            };
+            // Check if the file exists
+            if file_path.exists() {
+                // Move the file to the trash
+                match move_to_trash(&file_path, &self.folderdir) {
+                    Some(trash_path) => self.last_removed.push(trash_path),
+                    None => {
+                        log::warn!("Error moving {file_path:?} to the trash. Skip removing file.");
+                        continue;
+                    }
                 }
             }
-            // All the pointers in `loaded_paths` larger than the removed `fp_pointer` need to be
-            // shifted down by one
-            self.loaded_paths = self
-                .loaded_paths
-                .iter()
-                .map(|&x| if x > fp_pointer { x - 1 } else { x })
-                .collect();
-            // Remove it from the loader
-            self.valid_paths.remove(fp_pointer);
-            self.loaded_paths.remove(selected_idx);
-            self.papers.remove(selected_idx);
+            self.marked.remove(&file_path);
+            self.valid_paths.remove(path_idx);
+            removed_idxs.push(path_idx);
+        }
+        // If the selected paper was removed, select the paper that took its place. Papers that
+        // could not be moved to the trash are still there, so they do not count.
+        let removed_before = removed_idxs.iter().filter(|&&i| i < selected_idx).count();
+        return self.reload_window(window_start, selected_path, selected_idx - removed_before);
+    }
+    /// Restore the paper files removed by the last call to `Loader::remove_files` from the trash
+    /// and reload the valid paths. The first restored paper is selected. The new file pointer is
+    /// returned, together with the amount of papers that were actually restored.
+    pub fn undo_remove(&mut self, file_pointer: usize) -> (usize, usize) {
+        if self.last_removed.is_empty() {
+            return (file_pointer, 0);
+        }
+        let mut restored: Vec<std::path::PathBuf> = Vec::new();
+        for trash_path in self.last_removed.drain(..) {
+            if let Some(p) = restore_from_trash(&trash_path, &self.folderdir) {
+                restored.push(p);
+            }
+        }
+        let fallback_idx = self.path_index(file_pointer).unwrap_or(0);
+        let selected_path = match restored.first() {
+            Some(p) => Some(p.clone()),
+            None => self.valid_paths.get(fallback_idx).cloned(),
+        };
+        return (self.refresh(selected_path, fallback_idx), restored.len());
+    }
+    /// Look for the valid paper files in `folderdir` again, e.g. after files were changed on
+    /// disk, and reload the loaded window. The paper pointed at by `file_pointer` stays selected
+    /// if it is still valid. The new file pointer is returned.
+    pub fn reload(&mut self, file_pointer: usize) -> usize {
+        let fallback_idx = self.path_index(file_pointer).unwrap_or(0);
+        let selected_path = self.valid_paths.get(fallback_idx).cloned();
+        return self.refresh(selected_path, fallback_idx);
+    }
+    /// Only show the papers that have all the tags in `tag_filters`, and reload the loaded
+    /// window. The paper pointed at by `file_pointer` stays selected if it has those tags,
+    /// otherwise the first paper is selected. The new file pointer is returned.
+    pub fn set_tag_filters(&mut self, tag_filters: Vec<String>, file_pointer: usize) -> usize {
+        let selected_path = self
+            .path_index(file_pointer)
+            .and_then(|i| self.valid_paths.get(i).cloned());
+        self.tag_filters = tag_filters;
+        return self.refresh(selected_path, 0);
+    }
+    /// Look for the valid paper files in `folderdir` again and reload the loaded window. The
+    /// paper at `selected_path` is selected if it is still valid, otherwise the paper at index
+    /// `fallback_idx` of the new `valid_paths` is selected. The new file pointer is returned.
+    pub fn refresh(
+        &mut self,
+        selected_path: Option<std::path::PathBuf>,
+        fallback_idx: usize,
+    ) -> usize {
+        let window_start = self.loaded_paths.front().cloned().unwrap_or(0);
+        self.valid_paths =
+            get_all_valid_filepaths(&self.folderdir, &self.tag_filters, &self.tag_normalizer);
+        // Forget marks of papers that no longer exist.
+        let valid_paths = &self.valid_paths;
+        self.marked.retain(|p| valid_paths.contains(p));
+        return self.reload_window(window_start, selected_path, fallback_idx);
+    }
+    /// Load the window of papers starting as close to `window_start` as possible, while making
+    /// sure the paper at `selected_path` is inside the window. If that paper no longer exists, the
+    /// paper at index `fallback_idx` of `valid_paths` is used instead. All papers are read from
+    /// disk again, since `valid_paths` may have changed. The file pointer to the selected paper is
+    /// returned.
+    pub fn reload_window(
+        &mut self,
+        window_start: usize,
+        selected_path: Option<std::path::PathBuf>,
+        fallback_idx: usize,
+    ) -> usize {
+        if self.valid_paths.is_empty() {
+            self.reload_window_from_disk(0);
+            return 0;
+        }
+        let window_len = self.window_len();
+        let selected_idx =
+            match selected_path.and_then(|p| self.valid_paths.iter().position(|v| *v == p)) {
+                Some(i) => i,
+                None => fallback_idx.min(self.valid_paths.len() - 1),
+            };
+        // Keep the window filled if possible, and move it so the selected paper is inside it.
+        let mut start = window_start.min(self.valid_paths.len().saturating_sub(window_len));
+        if selected_idx < start || selected_idx >= start + window_len {
+            start = selected_idx.min(self.valid_paths.len().saturating_sub(window_len));
+        }
+        self.reload_window_from_disk(start);
+        return self
+            .loaded_paths
+            .iter()
+            .position(|&l| l >= selected_idx)
+            .unwrap_or(self.papers.len().saturating_sub(1));
+    }
+    /// Get the paper at index `path_idx` of `valid_paths`, from the loaded papers if possible
+    /// and from its file otherwise.
+    pub fn get_paper(&self, path_idx: usize) -> Option<Paper> {
+        if let Some(i) = self.loaded_paths.iter().position(|&l| l == path_idx) {
+            return self.papers.get(i).cloned();
+        }
+        return self.valid_paths.get(path_idx).and_then(parse_paper_toml);
+    }
+    /// Check if the paper at index `path_idx` of `valid_paths` is marked.
+    pub fn is_marked(&self, path_idx: usize) -> bool {
+        match self.valid_paths.get(path_idx) {
+            Some(p) => return self.marked.contains(p),
+            None => return false,
+        }
+    }
+    /// Mark the paper at index `path_idx` of `valid_paths` if it is not marked, and unmark it
+    /// otherwise.
+    pub fn toggle_mark(&mut self, path_idx: usize) {
+        if let Some(p) = self.valid_paths.get(path_idx) {
+            if !self.marked.remove(p) {
+                self.marked.insert(p.clone());
+            }
+        }
+    }
+    /// Mark all the papers from index `from` up to and including index `to` of `valid_paths`.
+    /// The order of `from` and `to` does not matter.
+    pub fn mark_range(&mut self, from: usize, to: usize) {
+        let (low, high) = if from <= to { (from, to) } else { (to, from) };
+        for path_idx in low..=high {
+            if let Some(p) = self.valid_paths.get(path_idx) {
+                self.marked.insert(p.clone());
+            }
+        }
+    }
+    /// Get the indices in `valid_paths` of all the marked papers, in ascending order.
+    pub fn marked_indices(&self) -> Vec<usize> {
+        return self
+            .valid_paths
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| self.marked.contains(*p))
+            .map(|(i, _)| i)
+            .collect();
+    }
+    /// Get the indices in `valid_paths` of the papers an action should be applied to. These are
+    /// the marked papers if there are any, and the paper pointed at by `selected_idx` otherwise.
+    pub fn target_indices(&self, selected_idx: usize) -> Vec<usize> {
+        let marked = self.marked_indices();
+        if !marked.is_empty() {
+            return marked;
+        }
+        match self.path_index(selected_idx) {
+            Some(i) => return vec![i],
+            None => return Vec::new(),
+        }
+    }
+    /// Copy the citation keys of the papers at the indices `path_idxs` of `valid_paths` to the
+    /// system clipboard, separated by commas so they can be pasted into a single `\cite{}`.
+    pub fn citation_keys_to_clipboard(&self, path_idxs: &[usize]) -> ActionResult {
+        let keys: Vec<String> = path_idxs
+            .iter()
+            .filter_map(|&i| self.get_paper(i))
+            .filter_map(|p| extract_citation_key(&p.bibtex))
+            .collect();
+        if keys.is_empty() {
+            log::warn!("None of the selected papers has a citation key. Stop copying citation keys to clipboard.");
+            return Err(String::from("No citation keys found"));
+        }
+        // Get clipboard context
+        let mut ctx = match ClipboardContext::new() {
+            Ok(c) => c,
+            Err(err) => {
+                log::warn!("Error obtaining clipboard context: {err}");
+                return Err(format!("Clipboard unavailable: {err}"));
+            }
+        };
+        // Set contents
+        let count = keys.len();
+        match ctx.set_contents(keys.join(",")) {
+            Ok(_) => return Ok(format!("Copied {} citation key(s)", count)),
+            Err(err) => {
+                log::warn!("Error setting clipboard contents: {err}");
+                return Err(format!("Error copying citation keys: {err}"));
+            }
+        }
+    }
+    /// Write the bibtex entries of the papers at the indices `path_idxs` of `valid_paths` to the
+    /// file at `file_path`, separated by an empty line. An existing file is overwritten.
+    pub fn export_bibtex(
+        &self,
+        path_idxs: &[usize],
+        file_path: &std::path::PathBuf,
+    ) -> ActionResult {
+        let entries: Vec<String> = path_idxs
+            .iter()
+            .filter_map(|&i| self.get_paper(i))
+            .map(|p| p.bibtex.trim().to_string())
+            .filter(|b| !b.is_empty())
+            .collect();
+        let file_path = expand_filepath(file_path);
+        match std::fs::write(&file_path, entries.join("\n\n") + "\n") {
+            Ok(_) => {
+                return Ok(format!(
+                    "Exported {} bibtex entries to {:?}",
+                    entries.len(),
+                    file_path
+                ))
+            }
+            Err(err) => {
+                log::warn!("Error writing bibtex entries to {file_path:?}: {err}");
+                return Err(format!("Error writing {:?}: {err}", file_path));
+            }
         }
     }
 }
 
-/// Compute the loader size based on the terminal size.
-pub fn compute_loader_size() -> i32 {
-    // First: -1.0 because of master block title
-    // Second: -2.0*2.0 because margin(2) on top and bottom
-    // Third: * 85.0 / 100.0 because explorer takes up 85% of the window
-    // Fourth: -1.0 because of block title.
-    // Fifth: -1.0 as a buffer so we are certainly not overloaded.
-    match termsize::get() {
-        Some(size) => {
-            let tui_rows = ((size.rows as f32 - 1.0) - 2.0 * 2.0) * 85.0 / 100.0 - 1.0 - 1.0;
-            let tui_rows = tui_rows.floor() as i32;
-            return tui_rows;
-        }
-        None => {
-            log::warn!("Could not obtain terminal size. Default value will be used.");
-            return 20;
+/// Extract the citation key from a bibtex entry, e.g. `dudal2024casimir` from
+/// `@article{dudal2024casimir, ...}`. Returns `None` if there is no key.
+pub fn extract_citation_key(bibtex: &str) -> Option<String> {
+    let re = match regex::Regex::new(r"@\s*\w+\s*\{\s*([^,\s]+)\s*,") {
+        Ok(r) => r,
+        Err(err) => {
+            log::error!("Error creating citation key regex: {err}");
+            return None;
         }
-    }
+    };
+    return re
+        .captures(bibtex)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str().to_string());
 }
 
 /// Given a file path that, check if the `$HOME` or `~` are used, and if so,
@@ -475,3 +792,207 @@ pub fn expand_filepath(path: &std::path::PathBuf) -> std::path::PathBuf {
         return path.to_path_buf();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::config::TagsFromFile;
+
+    /// Create a library in the temporary directory, unique to the test `name`, with a paper
+    /// file for every entry of `titles`, in that order. `None` gives a file that cannot be
+    /// parsed.
+    fn library(name: &str, titles: &[Option<&str>]) -> Library {
+        let folderdir =
+            std::env::temp_dir().join(format!("dumpling-loader-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&folderdir).unwrap();
+        for (i, title) in titles.iter().enumerate() {
+            let contents = match title {
+                Some(t) => format!("title = \"{}\"\n", t),
+                None => String::from("title = [broken\n"),
+            };
+            std::fs::write(folderdir.join(format!("{:02}.toml", i)), contents).unwrap();
+        }
+        return Library {
+            name: None,
+            folderdir,
+            pdf_dir: String::new(),
+        };
+    }
+
+    /// Library of `count` papers titled `P0`, `P1` and so on, where the papers at the
+    /// `broken` indices cannot be parsed.
+    fn numbered(name: &str, count: usize, broken: &[usize]) -> Library {
+        let titles: Vec<String> = (0..count).map(|i| format!("P{}", i)).collect();
+        let titles: Vec<Option<&str>> = titles
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (!broken.contains(&i)).then_some(t.as_str()))
+            .collect();
+        return library(name, &titles);
+    }
+
+    fn loader(library: &Library, load: i32) -> Loader {
+        let normalizer = TagNormalizer::new(&TagsFromFile::default());
+        return Loader::load(load, library, &None, &normalizer);
+    }
+
+    fn title(loader: &Loader, file_pointer: usize) -> String {
+        return loader.papers[file_pointer].title.clone();
+    }
+
+    fn selected_title(loader: &mut Loader, path_idx: usize, backwards: bool) -> String {
+        let file_pointer = loader.select(path_idx, backwards);
+        return title(loader, file_pointer);
+    }
+
+    fn loaded_titles(loader: &Loader) -> Vec<String> {
+        return loader.papers.iter().map(|p| p.title.clone()).collect();
+    }
+
+    /// Titles of the papers selected by stepping `steps` times with `move_by(delta)`,
+    /// starting from `file_pointer`.
+    fn step(loader: &mut Loader, file_pointer: usize, delta: isize, steps: usize) -> Vec<String> {
+        let mut file_pointer = file_pointer;
+        let mut titles = vec![title(loader, file_pointer)];
+        for _ in 0..steps {
+            file_pointer = loader.move_by(file_pointer, delta);
+            titles.push(title(loader, file_pointer));
+        }
+        return titles;
+    }
+
+    #[test]
+    fn empty_library() {
+        let library = numbered("empty", 0, &[]);
+        let mut loader = loader(&library, 3);
+        assert!(loader.papers.is_empty());
+        assert_eq!(loader.select(5, false), 0);
+        assert_eq!(loader.select(5, true), 0);
+        assert_eq!(loader.move_by(0, 1), 0);
+        assert_eq!(loader.move_by(0, -1), 0);
+        assert_eq!(loader.set_load_size(1, 0), 0);
+        assert_eq!(loader.reload(0), 0);
+        assert!(loader.papers.is_empty());
+        std::fs::remove_dir_all(&library.folderdir).unwrap();
+    }
+
+    #[test]
+    fn select_moves_the_window() {
+        let library = numbered("select", 10, &[]);
+        let mut loader = loader(&library, 3);
+        assert_eq!(loaded_titles(&loader), vec!["P0", "P1", "P2", "P3"]);
+        // Selecting the last index puts it at the bottom of the window.
+        let file_pointer = loader.select(9, false);
+        assert_eq!(file_pointer, 3);
+        assert_eq!(loaded_titles(&loader), vec!["P6", "P7", "P8", "P9"]);
+        // Indices past the end are clamped to the last paper.
+        assert_eq!(selected_title(&mut loader, 100, false), "P9");
+        // A loaded paper does not move the window.
+        assert_eq!(loader.select(7, true), 1);
+        assert_eq!(loaded_titles(&loader), vec!["P6", "P7", "P8", "P9"]);
+        // Going up puts the selected paper at the top.
+        assert_eq!(loader.select(2, true), 0);
+        assert_eq!(loaded_titles(&loader), vec!["P2", "P3", "P4", "P5"]);
+        std::fs::remove_dir_all(&library.folderdir).unwrap();
+    }
+
+    #[test]
+    fn stepping_stops_at_the_ends() {
+        let library = numbered("ends", 5, &[]);
+        let mut loader = loader(&library, 1);
+        assert_eq!(
+            step(&mut loader, 0, 1, 6),
+            vec!["P0", "P1", "P2", "P3", "P4", "P4", "P4"]
+        );
+        let file_pointer = loader.select(4, false);
+        assert_eq!(
+            step(&mut loader, file_pointer, -2, 3),
+            vec!["P4", "P2", "P0", "P0"]
+        );
+        std::fs::remove_dir_all(&library.folderdir).unwrap();
+    }
+
+    #[test]
+    fn stepping_skips_unparsable_papers() {
+        let library = numbered("broken", 10, &[2, 3, 4, 8, 9]);
+        let mut loader = loader(&library, 1);
+        assert_eq!(
+            step(&mut loader, 0, 1, 5),
+            vec!["P0", "P1", "P5", "P6", "P7", "P7"]
+        );
+        let file_pointer = loader.select(9, true);
+        assert_eq!(
+            step(&mut loader, file_pointer, -1, 5),
+            vec!["P7", "P6", "P5", "P1", "P0", "P0"]
+        );
+        std::fs::remove_dir_all(&library.folderdir).unwrap();
+    }
+
+    #[test]
+    fn unparsable_papers_at_the_start() {
+        let library = numbered("broken-start", 4, &[0, 1]);
+        let mut loader = loader(&library, 0);
+        assert_eq!(selected_title(&mut loader, 0, false), "P2");
+        assert_eq!(selected_title(&mut loader, 1, true), "P2");
+        assert_eq!(selected_title(&mut loader, 3, false), "P3");
+        std::fs::remove_dir_all(&library.folderdir).unwrap();
+    }
+
+    #[test]
+    fn only_unparsable_papers() {
+        let library = numbered("broken-all", 3, &[0, 1, 2]);
+        let mut loader = loader(&library, 1);
+        assert_eq!(loader.select(1, false), 0);
+        assert_eq!(loader.select(1, true), 0);
+        assert!(loader.papers.is_empty());
+        std::fs::remove_dir_all(&library.folderdir).unwrap();
+    }
+
+    #[test]
+    fn set_load_size_keeps_the_selection() {
+        let library = numbered("resize", 10, &[]);
+        let mut loader = loader(&library, 5);
+        let file_pointer = loader.select(5, false);
+        assert_eq!(file_pointer, 5);
+        // Shrinking moves the window so the selected paper stays inside it.
+        let file_pointer = loader.set_load_size(2, file_pointer);
+        assert_eq!(loaded_titles(&loader), vec!["P3", "P4", "P5"]);
+        assert_eq!(title(&loader, file_pointer), "P5");
+        // Growing fills the window from the start where possible.
+        let file_pointer = loader.set_load_size(7, file_pointer);
+        assert_eq!(loader.papers.len(), 8);
+        assert_eq!(loaded_titles(&loader)[0], "P2");
+        assert_eq!(title(&loader, file_pointer), "P5");
+        // A window that is larger than the library holds all the papers.
+        let file_pointer = loader.set_load_size(20, file_pointer);
+        assert_eq!(loader.papers.len(), 10);
+        assert_eq!(title(&loader, file_pointer), "P5");
+        // A load size of zero still holds the selected paper.
+        let file_pointer = loader.set_load_size(0, file_pointer);
+        assert_eq!(loaded_titles(&loader), vec!["P5"]);
+        assert_eq!(file_pointer, 0);
+        std::fs::remove_dir_all(&library.folderdir).unwrap();
+    }
+
+    #[test]
+    fn reload_keeps_or_replaces_the_selection() {
+        let library = numbered("reload", 6, &[]);
+        let mut loader = loader(&library, 2);
+        let file_pointer = loader.select(4, false);
+        assert_eq!(title(&loader, file_pointer), "P4");
+        // A paper added before the selected one does not change the selection.
+        std::fs::write(library.folderdir.join("00a.toml"), "title = \"New\"\n").unwrap();
+        let file_pointer = loader.reload(file_pointer);
+        assert_eq!(title(&loader, file_pointer), "P4");
+        // A removed paper is replaced by the paper that took its place.
+        std::fs::remove_file(library.folderdir.join("04.toml")).unwrap();
+        let file_pointer = loader.reload(file_pointer);
+        assert_eq!(title(&loader, file_pointer), "P5");
+        // Removing the last paper selects the new last paper.
+        std::fs::remove_file(library.folderdir.join("05.toml")).unwrap();
+        let file_pointer = loader.reload(file_pointer);
+        assert_eq!(title(&loader, file_pointer), "P3");
+        assert_eq!(loaded_titles(&loader), vec!["P1", "P2", "P3"]);
+        std::fs::remove_dir_all(&library.folderdir).unwrap();
+    }
+}
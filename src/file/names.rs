@@ -0,0 +1,565 @@
+use super::parser::Author;
+use crate::configuration::config::AuthorFormat;
+use unicode_normalization::UnicodeNormalization;
+
+/// Name BibTeX uses for the remaining authors of a paper, shown as "et al.".
+pub const OTHERS: &str = "others";
+
+/// Split `text` on the top level, i.e. outside of braces, wherever `is_separator` holds for
+/// a character. A character following a backslash is never a separator, so e.g. the `~` of
+/// the accent `\~n` is kept. Empty pieces are left out.
+fn split_top_level<F: Fn(char) -> bool>(text: &str, is_separator: F) -> Vec<String> {
+    let mut pieces: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut depth: usize = 0;
+    let mut escaped = false;
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if depth == 0 && !escaped && is_separator(c) {
+            pieces.push(current.trim().to_string());
+            current.clear();
+        } else {
+            current.push(c);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    pieces.push(current.trim().to_string());
+    return pieces.into_iter().filter(|p| !p.is_empty()).collect();
+}
+
+/// Split the author field of a BibTeX entry into the names of the authors, which are
+/// separated by the word `and` outside of braces, e.g. `{Barnes and Noble}` is one name.
+pub fn split_names(field: &str) -> Vec<String> {
+    let words = split_top_level(field, |c| c.is_whitespace() || c == '~');
+    let mut names: Vec<String> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    for word in words {
+        if word.eq_ignore_ascii_case("and") {
+            if !current.is_empty() {
+                names.push(current.join(" "));
+                current.clear();
+            }
+        } else {
+            current.push(word);
+        }
+    }
+    if !current.is_empty() {
+        names.push(current.join(" "));
+    }
+    return names;
+}
+
+/// Check if a word of a name starts with a lower case letter, which makes it part of the
+/// particle, e.g. `van` or `der`. Only letters outside of braces count, so `{van}` is not.
+/// Like in BibTeX, a group starting with a backslash, e.g. `{\"U}`, is a special character,
+/// which counts as a letter of the case of the letter it holds.
+fn is_lowercase_word(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    let mut depth: usize = 0;
+    for (i, c) in chars.iter().enumerate() {
+        match c {
+            '{' if depth == 0 && chars.get(i + 1) == Some(&'\\') => {
+                return is_lowercase_special(&chars[i + 2..]);
+            }
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && c.is_alphabetic() => return c.is_lowercase(),
+            _ => {}
+        }
+    }
+    return false;
+}
+
+/// Check if the special character starting with the `command` right after the backslash,
+/// e.g. `"U}` or `v{s}}`, is lower case. This is the case of the first letter after the
+/// command, or of the command itself if it is a letter on its own, e.g. `ss` or `OE`.
+fn is_lowercase_special(command: &[char]) -> bool {
+    let name_len = match command
+        .iter()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .count()
+    {
+        0 => command.len().min(1),
+        n => n,
+    };
+    for c in command[name_len..].iter() {
+        if c.is_alphabetic() {
+            return c.is_lowercase();
+        }
+        if *c == '}' {
+            break;
+        }
+    }
+    return match command.first() {
+        Some(c) => c.is_lowercase(),
+        None => false,
+    };
+}
+
+/// Get the combining accent the LaTeX accent `command` puts on a letter, e.g. `\u{308}` for
+/// the `"` of `\"u`.
+fn latex_accent(command: &str) -> Option<char> {
+    match command {
+        "\"" => return Some('\u{308}'),
+        "'" => return Some('\u{301}'),
+        "`" => return Some('\u{300}'),
+        "^" => return Some('\u{302}'),
+        "~" => return Some('\u{303}'),
+        "=" => return Some('\u{304}'),
+        "." => return Some('\u{307}'),
+        "u" => return Some('\u{306}'),
+        "v" => return Some('\u{30C}'),
+        "H" => return Some('\u{30B}'),
+        "r" => return Some('\u{30A}'),
+        "c" => return Some('\u{327}'),
+        "k" => return Some('\u{328}'),
+        "d" => return Some('\u{323}'),
+        "b" => return Some('\u{331}'),
+        _ => return None,
+    }
+}
+
+/// Get the letter written by the LaTeX `command`, e.g. `ß` for `\ss`.
+fn latex_letter(command: &str) -> Option<&'static str> {
+    match command {
+        "ss" => return Some("ß"),
+        "o" => return Some("ø"),
+        "O" => return Some("Ø"),
+        "ae" => return Some("æ"),
+        "AE" => return Some("Æ"),
+        "oe" => return Some("œ"),
+        "OE" => return Some("Œ"),
+        "aa" => return Some("å"),
+        "AA" => return Some("Å"),
+        "l" => return Some("ł"),
+        "L" => return Some("Ł"),
+        "i" => return Some("ı"),
+        "j" => return Some("ȷ"),
+        _ => return None,
+    }
+}
+
+/// Convert the LaTeX accents and letters in `text` into Unicode, e.g. `M{\"u}ller` becomes
+/// `M{ü}ller` and `{\ss}` becomes `{ß}`. Other commands are kept as they are.
+fn latex_to_unicode(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut converted = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '\\' || i + 1 == chars.len() {
+            converted.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        // Read the command name, a run of letters or a single other character.
+        let start = i + 1;
+        let mut end = start;
+        while end < chars.len() && chars[end].is_ascii_alphabetic() {
+            end += 1;
+        }
+        if end == start {
+            end = start + 1;
+        }
+        let command: String = chars[start..end].iter().collect();
+        let mut next = end;
+        // Spaces after a command made of letters belong to the command.
+        if chars[start].is_ascii_alphabetic() {
+            while next < chars.len() && chars[next] == ' ' {
+                next += 1;
+            }
+        }
+        if let Some(letter) = latex_letter(&command) {
+            converted.push_str(letter);
+            // Drop an empty group ending the command, as in `\ss{}`.
+            if chars.get(next) == Some(&'{') && chars.get(next + 1) == Some(&'}') {
+                next += 2;
+            }
+            i = next;
+            continue;
+        }
+        let accent = match latex_accent(&command) {
+            Some(a) => a,
+            None => {
+                converted.push('\\');
+                converted.push_str(&command);
+                i = end;
+                continue;
+            }
+        };
+        // The accented letter is either the next character or the contents of a group.
+        let argument: String = if chars.get(next) == Some(&'{') {
+            // The group ends at the brace matching the opening one, as in `\v{{s}}`.
+            let mut depth = 0;
+            let mut close = chars.len();
+            for (j, c) in chars.iter().enumerate().skip(next) {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => continue,
+                }
+                if depth == 0 {
+                    close = j;
+                    break;
+                }
+            }
+            let argument = chars[next + 1..close].iter().collect();
+            i = (close + 1).min(chars.len());
+            argument
+        } else if next < chars.len() {
+            i = next + 1;
+            chars[next].to_string()
+        } else {
+            i = next;
+            String::new()
+        };
+        let argument = latex_to_unicode(&argument).replace(['{', '}'], "");
+        let mut argument_chars = argument.chars();
+        match argument_chars.next() {
+            Some(letter) => {
+                // Accents are written on the dotless letters, as in `\"{\i}`, but belong
+                // on the normal ones.
+                match letter {
+                    'ı' => converted.push('i'),
+                    'ȷ' => converted.push('j'),
+                    _ => converted.push(letter),
+                }
+                converted.push(accent);
+                converted.extend(argument_chars);
+            }
+            None => converted.push(accent),
+        }
+    }
+    return converted.nfc().collect();
+}
+
+/// Join the `words` of a name part, with LaTeX accents converted into Unicode and the braces
+/// removed.
+fn join_words(words: &[String]) -> String {
+    return latex_to_unicode(&words.join(" ")).replace(['{', '}'], "");
+}
+
+/// Split the words of the `von Last` part of a name into the particle and the family name.
+/// The particle runs up to the last lower case word, but the last word is always part of
+/// the family name.
+fn split_particle(words: &[String]) -> (String, String) {
+    let last_lowercase = words[..words.len().saturating_sub(1)]
+        .iter()
+        .rposition(|w| is_lowercase_word(w));
+    match last_lowercase {
+        Some(i) => return (join_words(&words[..=i]), join_words(&words[i + 1..])),
+        None => return (String::new(), join_words(words)),
+    }
+}
+
+/// Parse a single name following the BibTeX rules. The name can be written as
+/// `First von Last`, `von Last, First` or `von Last, Jr, First`, where the `von` part is made
+/// up of the words starting with a lower case letter. Text inside braces is kept together, so
+/// a corporate author like `{Barnes and Noble, Inc.}` becomes the family name as a whole.
+pub fn parse_name(text: &str) -> Author {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case(OTHERS) {
+        return Author {
+            name: OTHERS.to_string(),
+            ..Author::default()
+        };
+    }
+    let parts: Vec<Vec<String>> = split_top_level(text, |c| c == ',')
+        .iter()
+        .map(|p| split_top_level(p, |c| c.is_whitespace() || c == '~'))
+        .collect();
+    let mut author = Author::default();
+    match parts.len() {
+        0 => {}
+        1 => {
+            let words = &parts[0];
+            if words.is_empty() {
+                return Author::default();
+            }
+            // The given names run up to the first lower case word, but the last word is
+            // always part of the family name.
+            let first_lowercase = words[..words.len() - 1]
+                .iter()
+                .position(|w| is_lowercase_word(w));
+            let given_end = match first_lowercase {
+                Some(i) => i,
+                None => words.len() - 1,
+            };
+            author.given = join_words(&words[..given_end]);
+            let (particle, family) = split_particle(&words[given_end..]);
+            author.particle = particle;
+            author.family = family;
+        }
+        _ => {
+            let (particle, family) = split_particle(&parts[0]);
+            author.particle = particle;
+            author.family = family;
+            if parts.len() == 2 {
+                author.given = join_words(&parts[1]);
+            } else {
+                author.suffix = join_words(&parts[1]);
+                author.given = join_words(&parts[2..].concat());
+            }
+        }
+    }
+    author.name = author.display(AuthorFormat::Full);
+    return author;
+}
+
+/// Get the initials of the given names, e.g. `J. D.` for `Johannes Diderik` and `J.-P.`
+/// for `Jean-Paul`. Names that are already initials are kept as they are.
+fn initials(given: &str) -> String {
+    return given
+        .split_whitespace()
+        .map(|word| {
+            word.split('-')
+                .filter_map(|part| part.chars().next())
+                .map(|c| format!("{}.", c))
+                .collect::<Vec<String>>()
+                .join("-")
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+}
+
+/// Join the non-empty `parts` with spaces.
+fn join_parts(parts: &[&str]) -> String {
+    return parts
+        .iter()
+        .filter(|p| !p.is_empty())
+        .cloned()
+        .collect::<Vec<&str>>()
+        .join(" ");
+}
+
+impl Author {
+    /// Create an author from a name as written in BibTeX, see `parse_name`.
+    pub fn parse(text: &str) -> Self {
+        return parse_name(text);
+    }
+
+    /// Check if this is the `others` of a BibTeX author list.
+    pub fn is_others(&self) -> bool {
+        return self.name.eq_ignore_ascii_case(OTHERS);
+    }
+
+    /// Get the author with the parts of the name filled in. Paper files written before the
+    /// parts existed only have the `name`, which is parsed in that case.
+    pub fn parts(&self) -> Self {
+        if !self.family.is_empty() || self.name.trim().is_empty() || self.is_others() {
+            return self.clone();
+        }
+        let mut author = parse_name(&self.name);
+        author.name = self.name.clone();
+        return author;
+    }
+
+    /// Get the name of the author in the given `format`.
+    pub fn display(&self, format: AuthorFormat) -> String {
+        if self.is_others() {
+            return String::from("et al.");
+        }
+        let a = self.parts();
+        let family = join_parts(&[&a.particle, &a.family]);
+        match format {
+            AuthorFormat::Full => return join_parts(&[&a.given, &family, &a.suffix]),
+            AuthorFormat::Initials => {
+                return join_parts(&[&initials(&a.given), &family, &a.suffix]);
+            }
+            AuthorFormat::FamilyGiven => {
+                let mut name = family;
+                for part in [&a.given, &a.suffix] {
+                    if !part.is_empty() {
+                        name.push_str(", ");
+                        name.push_str(part);
+                    }
+                }
+                return name;
+            }
+            AuthorFormat::Family => return family,
+        }
+    }
+
+    /// Get the name of the author as written in BibTeX, which `parse_name` turns back into
+    /// the same parts, e.g. `van der Waals, Jr., Johannes Diderik`.
+    pub fn bibtex(&self) -> String {
+        if self.is_others() {
+            return OTHERS.to_string();
+        }
+        let a = self.parts();
+        // Brace a family name that would otherwise be split up differently, e.g. the lower
+        // case `van` of `{van Gogh}` that would become the particle.
+        let words: Vec<&str> = a.family.split_whitespace().collect();
+        let family = if a.family.contains(',')
+            || (words.len() > 1
+                && (a.given.is_empty() || words.iter().any(|w| is_lowercase_word(w))))
+        {
+            format!("{{{}}}", a.family)
+        } else {
+            a.family.clone()
+        };
+        let von_last = join_parts(&[&a.particle, &family]);
+        if !a.suffix.is_empty() {
+            return format!("{}, {}, {}", von_last, a.suffix, a.given);
+        }
+        if !a.given.is_empty() {
+            return format!("{}, {}", von_last, a.given);
+        }
+        return von_last;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn author(given: &str, particle: &str, family: &str, suffix: &str) -> Author {
+        let mut author = Author {
+            name: String::new(),
+            given: given.to_string(),
+            particle: particle.to_string(),
+            family: family.to_string(),
+            suffix: suffix.to_string(),
+        };
+        author.name = author.display(AuthorFormat::Full);
+        return author;
+    }
+
+    #[test]
+    fn first_von_last() {
+        assert_eq!(
+            parse_name("Johannes Diderik van der Waals"),
+            author("Johannes Diderik", "van der", "Waals", "")
+        );
+        assert_eq!(
+            parse_name("Ada Lovelace"),
+            author("Ada", "", "Lovelace", "")
+        );
+        assert_eq!(parse_name("Aristotle"), author("", "", "Aristotle", ""));
+        assert_eq!(
+            parse_name("Jean~de~La~Fontaine"),
+            author("Jean", "de", "La Fontaine", "")
+        );
+    }
+
+    #[test]
+    fn von_last_jr_first() {
+        assert_eq!(
+            parse_name("van der Waals, Johannes Diderik"),
+            author("Johannes Diderik", "van der", "Waals", "")
+        );
+        assert_eq!(
+            parse_name("King, Jr., Martin Luther"),
+            author("Martin Luther", "", "King", "Jr.")
+        );
+        assert_eq!(
+            parse_name("de la Vallée Poussin, Jr, Charles"),
+            author("Charles", "de la", "Vallée Poussin", "Jr")
+        );
+    }
+
+    #[test]
+    fn braced_corporate_names() {
+        assert_eq!(
+            parse_name("{Barnes and Noble, Inc.}"),
+            author("", "", "Barnes and Noble, Inc.", "")
+        );
+        assert_eq!(
+            split_names("{Barnes and Noble} and Jane Doe"),
+            vec!["{Barnes and Noble}", "Jane Doe"]
+        );
+        assert_eq!(
+            parse_name("Vincent {van Gogh}"),
+            author("Vincent", "", "van Gogh", "")
+        );
+    }
+
+    #[test]
+    fn others() {
+        let others = parse_name("others");
+        assert!(others.is_others());
+        assert_eq!(others.display(AuthorFormat::Full), "et al.");
+        assert_eq!(others.bibtex(), "others");
+        assert_eq!(
+            split_names("Ada Lovelace and others"),
+            vec!["Ada Lovelace", "others"]
+        );
+    }
+
+    #[test]
+    fn special_characters() {
+        assert_eq!(
+            parse_name("{\\\"U}lfur J{\\'o}nsson"),
+            author("Ülfur", "", "Jónsson", "")
+        );
+        assert_eq!(
+            parse_name("Hans M{\\\"u}ller"),
+            author("Hans", "", "Müller", "")
+        );
+        assert_eq!(
+            parse_name("{\\'e}mile {\\v{S}}koda"),
+            author("", "émile", "Škoda", "")
+        );
+        assert_eq!(
+            parse_name("Pe\\~na, Jos\\'e"),
+            author("José", "", "Peña", "")
+        );
+        assert_eq!(
+            parse_name("{\\OE}rsted, Hans Christian"),
+            author("Hans Christian", "", "Œrsted", "")
+        );
+        assert_eq!(
+            parse_name("Hans {\\o}ster Berg"),
+            author("Hans", "øster", "Berg", "")
+        );
+        assert_eq!(
+            parse_name("Hans Stra{\\ss}e"),
+            author("Hans", "", "Straße", "")
+        );
+    }
+
+    #[test]
+    fn nested_groups() {
+        assert_eq!(
+            parse_name("Lo{\\\"{\\i}}c Durand"),
+            author("Loïc", "", "Durand", "")
+        );
+        assert_eq!(
+            parse_name("Novotn\\'{{y}}, Ale\\v{{s}}"),
+            author("Aleš", "", "Novotný", "")
+        );
+        assert_eq!(
+            parse_name("Ale{\\v{{s}}} Nov{\\'{a}}k"),
+            author("Aleš", "", "Novák", "")
+        );
+        assert_eq!(latex_to_unicode("\\v{{s}}"), "š");
+        assert_eq!(latex_to_unicode("{\\\"{\\i}}"), "{ï}");
+    }
+
+    #[test]
+    fn names_without_words() {
+        assert_eq!(parse_name("~"), Author::default());
+        assert_eq!(parse_name(""), Author::default());
+        assert_eq!(parse_name("~, Jr, Ada").given, "Ada");
+    }
+
+    #[test]
+    fn bibtex_round_trip() {
+        for a in [
+            author("Johannes Diderik", "van der", "Waals", ""),
+            author("Martin Luther", "", "King", "Jr."),
+            author("", "", "Barnes and Noble, Inc.", ""),
+            author("", "", "World Health Organization", ""),
+            author("Vincent", "", "van Gogh", ""),
+            author("Per", "", "Brinch Hansen", ""),
+            author("Ülfur", "", "Jónsson", ""),
+            author("Charles", "de la", "Vallée Poussin", "Jr"),
+            parse_name("others"),
+        ] {
+            assert_eq!(parse_name(&a.bibtex()), a);
+        }
+    }
+}
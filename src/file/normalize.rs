@@ -0,0 +1,281 @@
+use super::parser::TAG_SEPARATOR;
+use crate::configuration::config::TagsFromFile;
+use std::collections::HashMap;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Decides which tags are the same tag, following the [tags] section of the
+/// configuration file. Tags are compared level by level, see `TAG_SEPARATOR`.
+///
+/// The `settings` field contains the normalisation options, and the `aliases`
+/// field the configured aliases, keyed by the normalised levels of the alias,
+/// mapping onto the canonical tag.
+#[derive(Clone, Debug)]
+pub struct TagNormalizer {
+    settings: TagsFromFile,
+    aliases: HashMap<Vec<String>, String>,
+}
+
+impl Default for TagNormalizer {
+    fn default() -> Self {
+        return TagNormalizer::new(&TagsFromFile::default());
+    }
+}
+
+impl TagNormalizer {
+    /// Create a `TagNormalizer` from the [tags] section of the configuration.
+    pub fn new(settings: &TagsFromFile) -> Self {
+        let mut normalizer = TagNormalizer {
+            settings: settings.clone(),
+            aliases: HashMap::new(),
+        };
+        for (alias, canonical) in settings.aliases.iter() {
+            let key = normalizer.levels_key(&normalizer.clean(alias));
+            let canonical = normalizer.clean(canonical);
+            normalizer.aliases.insert(key, canonical);
+        }
+        return normalizer;
+    }
+
+    /// Clean up the spelling of `tag`: empty levels are removed, and if `trim_whitespace`
+    /// is set, whitespace around each level and repeated whitespace inside it as well.
+    pub fn clean(&self, tag: &str) -> String {
+        let levels: Vec<String> = tag
+            .split(TAG_SEPARATOR)
+            .map(|level| {
+                if self.settings.trim_whitespace {
+                    level.split_whitespace().collect::<Vec<&str>>().join(" ")
+                } else {
+                    level.to_string()
+                }
+            })
+            .filter(|level| !level.is_empty())
+            .collect();
+        return levels.join(&TAG_SEPARATOR.to_string());
+    }
+
+    /// Get the canonical spelling of `tag`: the cleaned up tag, with the longest parent that
+    /// is an alias replaced by the tag it stands for, e.g. `QFT/casimir` becomes
+    /// `physics/qft/casimir` with the alias `QFT = "physics/qft"`.
+    pub fn resolve(&self, tag: &str) -> String {
+        let tag = self.clean(tag);
+        let levels: Vec<&str> = tag.split(TAG_SEPARATOR).collect();
+        for depth in (1..=levels.len()).rev() {
+            let parent = levels[..depth].join(&TAG_SEPARATOR.to_string());
+            if let Some(canonical) = self.aliases.get(&self.levels_key(&parent)) {
+                let mut resolved = canonical.clone();
+                for level in levels[depth..].iter() {
+                    resolved.push(TAG_SEPARATOR);
+                    resolved.push_str(level);
+                }
+                return resolved;
+            }
+        }
+        return tag;
+    }
+
+    /// Get the levels of `tag` in the form they are compared in.
+    pub fn key(&self, tag: &str) -> Vec<String> {
+        return self.levels_key(&self.resolve(tag));
+    }
+
+    /// Get the levels of the cleaned up `tag` with case and accents removed, as configured.
+    fn levels_key(&self, tag: &str) -> Vec<String> {
+        return tag
+            .split(TAG_SEPARATOR)
+            .filter(|level| !level.is_empty())
+            .map(|level| {
+                // Compose the level, so e.g. an `e` followed by a combining accent is the
+                // same as `é`.
+                let mut level: String = level.nfc().collect();
+                if self.settings.ignore_accents {
+                    level = strip_accents(&level);
+                }
+                if self.settings.ignore_case {
+                    level = level.to_lowercase();
+                }
+                level
+            })
+            .collect();
+    }
+
+    /// Check if `a` and `b` are the same tag.
+    pub fn same(&self, a: &str, b: &str) -> bool {
+        return self.key(a) == self.key(b);
+    }
+
+    /// Check if the tag `label` matches the `filter`, which is the case if it is the same
+    /// tag or if the filter is one of its parents, e.g. `physics` matches `physics/qft`.
+    pub fn matches(&self, label: &str, filter: &str) -> bool {
+        return self.strip_parent(label, filter).is_some();
+    }
+
+    /// If the tag `label` matches the `filter`, see `TagNormalizer::matches`, get the part of
+    /// the canonical label below the filter, starting with the separator, or an empty string
+    /// if the label is the filter itself. None is returned if the label does not match.
+    pub fn strip_parent(&self, label: &str, filter: &str) -> Option<String> {
+        let label = self.resolve(label);
+        let label_key = self.levels_key(&label);
+        let filter_key = self.key(filter);
+        if filter_key.is_empty() || !label_key.starts_with(&filter_key) {
+            return None;
+        }
+        let mut rest = String::new();
+        for level in label.split(TAG_SEPARATOR).skip(filter_key.len()) {
+            rest.push(TAG_SEPARATOR);
+            rest.push_str(level);
+        }
+        return Some(rest);
+    }
+
+    /// Get the spelling to store `tag` with: the spelling of a `known` tag that is the same
+    /// tag if there is one, so the existing spelling is reused, the canonical spelling otherwise.
+    pub fn canonical(&self, tag: &str, known: &[String]) -> String {
+        let key = self.key(tag);
+        match known.iter().find(|k| self.key(k) == key) {
+            Some(k) => return k.clone(),
+            None => return self.resolve(tag),
+        }
+    }
+}
+
+/// Remove the accents of the letters in `text`, e.g. `Québec` becomes `Quebec`. The text is
+/// decomposed, so `é` is an `e` followed by a combining accent, and the combining marks are
+/// dropped. Letters with a stroke or ligature, which do not decompose, are folded as well,
+/// e.g. `ø` becomes `o` and `ß` becomes `ss`.
+fn strip_accents(text: &str) -> String {
+    let mut stripped = String::new();
+    for c in text.nfd().filter(|c| !is_combining_mark(*c)) {
+        match fold_letter(c) {
+            Some(folded) => stripped.push_str(folded),
+            None => stripped.push(c),
+        }
+    }
+    return stripped;
+}
+
+/// Get the plain letters of a letter that does not decompose into a letter and an accent,
+/// or None for any other character.
+fn fold_letter(c: char) -> Option<&'static str> {
+    match c {
+        'Ø' => return Some("O"),
+        'ø' => return Some("o"),
+        'Đ' | 'Ð' => return Some("D"),
+        'đ' | 'ð' => return Some("d"),
+        'Ħ' => return Some("H"),
+        'ħ' => return Some("h"),
+        'ı' => return Some("i"),
+        'Ł' => return Some("L"),
+        'ł' => return Some("l"),
+        'Ŧ' => return Some("T"),
+        'ŧ' => return Some("t"),
+        'ß' => return Some("ss"),
+        'Æ' => return Some("AE"),
+        'æ' => return Some("ae"),
+        'Œ' => return Some("OE"),
+        'œ' => return Some("oe"),
+        _ => return None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn normalizer(
+        ignore_case: bool,
+        ignore_accents: bool,
+        aliases: &[(&str, &str)],
+    ) -> TagNormalizer {
+        let mut settings = TagsFromFile {
+            ignore_case,
+            ignore_accents,
+            trim_whitespace: true,
+            aliases: BTreeMap::new(),
+        };
+        for (alias, canonical) in aliases {
+            settings
+                .aliases
+                .insert(alias.to_string(), canonical.to_string());
+        }
+        return TagNormalizer::new(&settings);
+    }
+
+    #[test]
+    fn clean_trims_levels() {
+        let n = normalizer(false, false, &[]);
+        assert_eq!(
+            n.clean("  physics / quantum   field //qft "),
+            "physics/quantum field/qft"
+        );
+    }
+
+    #[test]
+    fn resolve_replaces_longest_alias_prefix() {
+        let n = normalizer(
+            true,
+            false,
+            &[("QFT", "physics/qft"), ("QFT/lattice", "lattice/qft")],
+        );
+        assert_eq!(n.resolve("qft"), "physics/qft");
+        assert_eq!(n.resolve("QFT/casimir"), "physics/qft/casimir");
+        assert_eq!(n.resolve("qft/Lattice/gauge"), "lattice/qft/gauge");
+        assert_eq!(n.resolve("math"), "math");
+    }
+
+    #[test]
+    fn key_ignores_case_and_accents() {
+        let n = normalizer(true, true, &[]);
+        assert_eq!(n.key("Québec/Ville"), vec!["quebec", "ville"]);
+        // Decomposed `e` followed by a combining acute accent.
+        assert!(n.same("Que\u{301}bec", "québec"));
+        assert!(n.same("Straße", "strasse"));
+        assert!(n.same("Œuvre", "oeuvre"));
+        assert!(n.same("Việt Nam", "viet nam"));
+    }
+
+    #[test]
+    fn key_keeps_case_and_accents_when_asked() {
+        let n = normalizer(false, false, &[]);
+        assert!(!n.same("QFT", "qft"));
+        assert!(!n.same("Québec", "Quebec"));
+        // The composed and decomposed forms are always the same tag.
+        assert!(n.same("Que\u{301}bec", "Québec"));
+    }
+
+    #[test]
+    fn key_resolves_aliases() {
+        let n = normalizer(true, false, &[("quantum field theory", "physics/qft")]);
+        assert_eq!(
+            n.key("Quantum Field Theory/casimir"),
+            vec!["physics", "qft", "casimir"]
+        );
+        assert!(n.same("quantum field theory", "Physics/QFT"));
+    }
+
+    #[test]
+    fn strip_parent_with_aliases() {
+        let n = normalizer(true, false, &[("QFT", "physics/qft")]);
+        assert_eq!(
+            n.strip_parent("physics/qft/casimir", "physics"),
+            Some(String::from("/qft/casimir"))
+        );
+        assert_eq!(
+            n.strip_parent("QFT/casimir", "physics/qft"),
+            Some(String::from("/casimir"))
+        );
+        assert_eq!(n.strip_parent("physics/qft", "qft"), Some(String::new()));
+        assert_eq!(n.strip_parent("physics", "physics/qft"), None);
+        assert_eq!(n.strip_parent("physical", "phys"), None);
+        assert!(n.matches("QFT/casimir", "Physics"));
+    }
+
+    #[test]
+    fn canonical_reuses_known_spelling() {
+        let n = normalizer(true, true, &[]);
+        let known = vec![String::from("Québec")];
+        assert_eq!(n.canonical("QUEBEC", &known), "Québec");
+        assert_eq!(n.canonical(" new  tag ", &known), "new tag");
+    }
+}
@@ -3,9 +3,19 @@ use sha2::{Digest, Sha256};
 use std::io::Read;
 
 /// Deserialization struct for parsing the paper Toml files.
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+///
+/// The `name` field contains the full name of the author. The `given`, `particle`
+/// (e.g. `van der`), `family` and `suffix` (e.g. `Jr.`) fields contain the parts of
+/// the name, see `names::parse_name`. They are empty for authors written before the
+/// parts existed, in which case they are taken from the `name`, see `Author::parts`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
 pub struct Author {
     pub name: String,
+    pub given: String,
+    pub particle: String,
+    pub family: String,
+    pub suffix: String,
 }
 
 /// Deserialization struct for parsing the paper Toml files.
@@ -14,8 +24,16 @@ pub struct Tag {
     pub label: String,
 }
 
+/// Character separating the levels of a hierarchical tag, e.g. `physics/qft/casimir`.
+pub const TAG_SEPARATOR: char = '/';
+
+/// Format of the date a paper was added on, e.g. `2024-08-17`.
+pub const ADDED_FORMAT: &str = "%Y-%m-%d";
+
 /// Main deserialization struct for parsing the paper Toml files.
-/// This includes vectors of the `Author` and `Tag` structs.
+/// This includes vectors of the `Author` and `Tag` structs. The `added` field holds
+/// the date the paper was added on, formatted with `ADDED_FORMAT`, and is empty for
+/// papers added before it existed.
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(default)]
 pub struct Paper {
@@ -25,6 +43,8 @@ pub struct Paper {
     pub description: String,
     pub bibtex: String,
     pub docname: String,
+    pub status: String,
+    pub added: String,
     pub authors: Vec<Author>,
     pub tags: Vec<Tag>,
 }
@@ -38,6 +58,8 @@ impl Default for Paper {
             description: String::new(),
             bibtex: String::new(),
             docname: String::new(),
+            status: String::new(),
+            added: String::new(),
             authors: Vec::new(),
             tags: Vec::new(),
         }
@@ -120,3 +142,329 @@ pub fn write_new_paper(paper: &Paper, folderdir: &std::path::PathBuf) {
         }
     }
 }
+
+/// Given the `original` instance of `Paper` read from an existing paper Toml file and the
+/// `paper` it was changed into, write only the keys whose value changed to that file, see
+/// `overwrite_paper_keys`. Unlike `write_new_paper`, the file name is kept as is so the `Loader`
+/// paths remain valid. Returns `false` if anything went wrong.
+pub fn overwrite_paper(original: &Paper, paper: &Paper, file_path: &std::path::PathBuf) -> bool {
+    // Convert both `Paper` structs into Toml tables to compare them key by key.
+    let (old_table, new_table) = match (
+        toml::Value::try_from(original),
+        toml::Value::try_from(paper),
+    ) {
+        (Ok(toml::Value::Table(o)), Ok(toml::Value::Table(n))) => (o, n),
+        _ => {
+            log::warn!("Error serializing `Paper` struct into Toml table");
+            return false;
+        }
+    };
+    let keys: Vec<&str> = new_table
+        .iter()
+        .filter(|(key, value)| old_table.get(*key) != Some(*value))
+        .map(|(key, _)| key.as_str())
+        .collect();
+    if keys.is_empty() {
+        return true;
+    }
+    return overwrite_paper_keys(paper, file_path, &keys);
+}
+
+/// Given a particular instance of `Paper` and the file path of an existing paper Toml file,
+/// set only the given `keys` of that file to their value in the `Paper` instance. The rest of
+/// the file, including comments, formatting and keys the `Paper` struct does not know, is left
+/// as it is. Returns `false` if anything went wrong.
+pub fn overwrite_paper_keys(paper: &Paper, file_path: &std::path::PathBuf, keys: &[&str]) -> bool {
+    // Convert the `Paper` struct into a Toml document to take the new values from.
+    let new_document = match toml::to_string(paper) {
+        Ok(s) => match s.parse::<toml_edit::DocumentMut>() {
+            Ok(d) => d,
+            Err(err) => {
+                log::warn!("Error parsing serialized `Paper` struct into Toml document: {err}");
+                return false;
+            }
+        },
+        Err(err) => {
+            log::warn!("Error serializing `Paper` struct into Toml formatted string: {err}");
+            return false;
+        }
+    };
+    // Read the current document
+    let contents = match std::fs::read_to_string(file_path) {
+        Ok(s) => s,
+        Err(err) => {
+            log::warn!("Error reading contents of {file_path:?}: {err}");
+            return false;
+        }
+    };
+    let mut document = match contents.parse::<toml_edit::DocumentMut>() {
+        Ok(d) => d,
+        Err(err) => {
+            log::warn!("Error parsing contents of {file_path:?} into Toml document: {err}");
+            return false;
+        }
+    };
+    for key in keys {
+        let new_item = match new_document.get(key) {
+            Some(item) => item.clone(),
+            None => {
+                document.remove(key);
+                continue;
+            }
+        };
+        // Keep lists written inline, e.g. `tags = [{ label = "x" }]`, inline.
+        let inline = matches!(document.get(key), Some(toml_edit::Item::Value(_)));
+        let mut new_item = match new_item {
+            toml_edit::Item::ArrayOfTables(tables) if inline => {
+                toml_edit::Item::Value(toml_edit::Value::Array(tables.into_array()))
+            }
+            item => item,
+        };
+        // Keep the spacing and comment around a replaced value, e.g. `title = "x"  # note`.
+        if let (Some(toml_edit::Item::Value(old)), toml_edit::Item::Value(new)) =
+            (document.get(key), &mut new_item)
+        {
+            *new.decor_mut() = old.decor().clone();
+        }
+        document[*key] = new_item;
+    }
+    // Save the file
+    match std::fs::write(file_path, document.to_string()) {
+        Ok(_) => {}
+        Err(err) => {
+            log::warn!("Error writing contents of `Paper` struct to {file_path:?}: {err}");
+            return false;
+        }
+    }
+    return true;
+}
+
+/// Fields of the `Paper` struct that can be edited from within the TUI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaperField {
+    Title,
+    Year,
+    Journal,
+    Description,
+    Bibtex,
+    Docname,
+    Status,
+    Authors,
+}
+
+/// Reading statuses offered as completions when setting the status of papers in the TUI. Any
+/// other status can be used as well.
+pub const READING_STATUSES: [&str; 3] = ["unread", "reading", "read"];
+
+/// Separator used to display and enter multiple authors as a single line of text. Every
+/// author is written as in BibTeX, see `Author::bibtex`.
+pub const AUTHOR_SEPARATOR: &str = "; ";
+
+impl PaperField {
+    /// All the editable fields, in the order they are presented to the user.
+    pub const ALL: [PaperField; 8] = [
+        PaperField::Title,
+        PaperField::Year,
+        PaperField::Journal,
+        PaperField::Description,
+        PaperField::Bibtex,
+        PaperField::Docname,
+        PaperField::Status,
+        PaperField::Authors,
+    ];
+
+    /// Name of the field as it appears in the paper Toml files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PaperField::Title => "title",
+            PaperField::Year => "year",
+            PaperField::Journal => "journal",
+            PaperField::Description => "description",
+            PaperField::Bibtex => "bibtex",
+            PaperField::Docname => "docname",
+            PaperField::Status => "status",
+            PaperField::Authors => "authors",
+        }
+    }
+
+    /// Parse the field from the text a user typed. Both the full name and any unambiguous
+    /// prefix of it are accepted, e.g. "desc" for `PaperField::Description`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        if name.is_empty() {
+            return None;
+        }
+        let matches: Vec<PaperField> = PaperField::ALL
+            .iter()
+            .filter(|f| f.name().starts_with(&name))
+            .cloned()
+            .collect();
+        if matches.len() == 1 {
+            return Some(matches[0]);
+        }
+        return PaperField::ALL.iter().find(|f| f.name() == name).cloned();
+    }
+
+    /// Check if the field of the given `Paper` is edited in the external editor rather than in
+    /// the single line pop-up, as its value may span multiple lines. This is always the case
+    /// for the BibTeX entry.
+    pub fn is_multiline(&self, paper: &Paper) -> bool {
+        return *self == PaperField::Bibtex || self.get(paper).contains('\n');
+    }
+
+    /// Get the current value of the field in the given `Paper` as a single line of text.
+    pub fn get(&self, paper: &Paper) -> String {
+        match self {
+            PaperField::Title => paper.title.clone(),
+            PaperField::Year => paper.year.to_string(),
+            PaperField::Journal => paper.journal.clone(),
+            PaperField::Description => paper.description.clone(),
+            PaperField::Bibtex => paper.bibtex.clone(),
+            PaperField::Docname => paper.docname.clone(),
+            PaperField::Status => paper.status.clone(),
+            PaperField::Authors => paper
+                .authors
+                .iter()
+                .map(|a| a.bibtex())
+                .collect::<Vec<String>>()
+                .join(AUTHOR_SEPARATOR),
+        }
+    }
+
+    /// Set the field in the given `Paper` from a single line of text. Returns `false` if the
+    /// text could not be interpreted, e.g. a year that is not an integer, in which case the
+    /// `Paper` is left untouched.
+    pub fn set(&self, paper: &mut Paper, value: &str) -> bool {
+        match self {
+            PaperField::Title => paper.title = value.to_string(),
+            PaperField::Year => match value.trim().parse::<i32>() {
+                Ok(y) => paper.year = y,
+                Err(err) => {
+                    log::warn!("Unable to parse {value:?} into a year: {err}");
+                    return false;
+                }
+            },
+            PaperField::Journal => paper.journal = value.to_string(),
+            PaperField::Description => paper.description = value.to_string(),
+            PaperField::Bibtex => paper.bibtex = value.to_string(),
+            PaperField::Docname => paper.docname = value.to_string(),
+            PaperField::Status => paper.status = value.trim().to_string(),
+            PaperField::Authors => {
+                paper.authors = value
+                    .split(AUTHOR_SEPARATOR.trim())
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .map(Author::parse)
+                    .collect();
+            }
+        }
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAPER: &str = r#"# Notes on this paper
+title = "Casimir effect"   # working title
+year = 2001
+custom = "kept as is"
+tags = [{ label = "physics" }, { label = "qft" }]
+bibtex = """
+@article{casimir,
+  title = {Casimir effect}
+}"""
+
+[[authors]]
+name = "Hendrik Casimir"
+"#;
+
+    /// Write `contents` to a paper file in the temporary directory, unique to the test `name`.
+    fn paper_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "dumpling-parser-{}-{}.toml",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, contents).unwrap();
+        return path;
+    }
+
+    #[test]
+    fn overwrite_paper_changes_only_edited_keys() {
+        let path = paper_file("edited", PAPER);
+        let original = parse_paper_toml(&path).unwrap();
+        let mut paper = original.clone();
+        paper.title = String::from("The Casimir effect");
+        assert!(overwrite_paper(&original, &paper, &path));
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            contents,
+            PAPER.replace(
+                r#"title = "Casimir effect"   # working title"#,
+                r#"title = "The Casimir effect"   # working title"#
+            )
+        );
+        assert_eq!(parse_paper_toml(&path), Some(paper));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn overwrite_paper_without_changes_keeps_file() {
+        let path = paper_file("unchanged", PAPER);
+        let original = parse_paper_toml(&path).unwrap();
+        assert!(overwrite_paper(&original, &original.clone(), &path));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), PAPER);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn overwrite_paper_keys_keeps_inline_arrays_inline() {
+        let path = paper_file("inline", PAPER);
+        let mut paper = parse_paper_toml(&path).unwrap();
+        paper.tags[1].label = String::from("physics/qft");
+        assert!(overwrite_paper_keys(&paper, &path, &["tags"]));
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains(r#"tags = [{ label = "physics" }, { label = "physics/qft" }]"#));
+        assert!(!contents.contains("[[tags]]"));
+        assert!(contents.starts_with("# Notes on this paper\n"));
+        assert!(contents.contains(r#"custom = "kept as is""#));
+        assert_eq!(parse_paper_toml(&path), Some(paper));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn overwrite_paper_keys_keeps_tables_of_arrays() {
+        let path = paper_file(
+            "tables",
+            "title = \"x\"\n\n[[tags]]\nlabel = \"a\"\n\n# last author\n[[authors]]\nname = \"A\"\n",
+        );
+        let mut paper = parse_paper_toml(&path).unwrap();
+        paper.tags.push(Tag {
+            label: String::from("b"),
+        });
+        assert!(overwrite_paper_keys(&paper, &path, &["tags"]));
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("[[tags]]\nlabel = \"a\"\n"));
+        assert!(contents.contains("[[tags]]\nlabel = \"b\"\n"));
+        assert!(contents.contains("# last author\n[[authors]]\nname = \"A\"\n"));
+        assert_eq!(parse_paper_toml(&path), Some(paper));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn overwrite_paper_adds_missing_keys() {
+        let path = paper_file("missing", "# only a title\ntitle = \"x\"\n");
+        let original = parse_paper_toml(&path).unwrap();
+        let mut paper = original.clone();
+        paper.status = String::from("read");
+        assert!(overwrite_paper(&original, &paper, &path));
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            contents,
+            "# only a title\ntitle = \"x\"\nstatus = \"read\"\n"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
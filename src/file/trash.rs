@@ -0,0 +1,196 @@
+use super::parser::parse_paper_toml;
+
+/// Name of the directory, inside the directory with the paper files, where deleted paper files
+/// are moved to.
+pub const TRASH_DIR: &str = ".trash";
+/// Format of the time stamp that is prepended to the file name of a deleted paper file.
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Summary of a paper file in the trash directory.
+///
+/// The `path` field contains the path to the file inside the trash directory.
+/// The `original_name` field contains the file name the paper file had before it was deleted.
+/// The `deleted` field contains the local time at which the paper file was deleted.
+/// The `title` field contains the title of the deleted paper, if it could be parsed.
+#[derive(Clone, Debug)]
+pub struct TrashEntry {
+    pub path: std::path::PathBuf,
+    pub original_name: String,
+    pub deleted: chrono::NaiveDateTime,
+    pub title: String,
+}
+
+/// Get the path to the trash directory belonging to the paper files in `folderdir`.
+pub fn trash_dir(folderdir: &std::path::PathBuf) -> std::path::PathBuf {
+    let mut dir = folderdir.clone();
+    dir.push(TRASH_DIR);
+    return dir;
+}
+
+/// Move the paper file at `file_path` into the trash directory of `folderdir`, creating that
+/// directory if it does not exist. The current time is prepended to the file name so the time of
+/// deletion is known. If the same file was already deleted within the same second, a counter is
+/// added to the time stamp, e.g. `20240817-101500.2`, so the earlier copy is kept. The path to
+/// the file in the trash is returned, or `None` if the file could not be moved.
+pub fn move_to_trash(
+    file_path: &std::path::PathBuf,
+    folderdir: &std::path::PathBuf,
+) -> Option<std::path::PathBuf> {
+    let dir = trash_dir(folderdir);
+    if !dir.exists() {
+        match std::fs::create_dir(&dir) {
+            Ok(_) => {}
+            Err(err) => {
+                log::warn!("Could not create trash directory {dir:?}: {err}");
+                return None;
+            }
+        }
+    }
+    let file_name = match file_path.file_name().and_then(|f| f.to_str()) {
+        Some(f) => f,
+        None => {
+            log::warn!("Could not obtain the file name of {file_path:?}. Stop moving it to trash.");
+            return None;
+        }
+    };
+    let timestamp = chrono::Local::now().format(TIMESTAMP_FORMAT).to_string();
+    let mut trash_path = dir.join(format!("{}_{}", timestamp, file_name));
+    let mut counter = 1;
+    while trash_path.exists() {
+        counter += 1;
+        trash_path = dir.join(format!("{}.{}_{}", timestamp, counter, file_name));
+    }
+    match std::fs::rename(file_path, &trash_path) {
+        Ok(_) => return Some(trash_path),
+        Err(err) => {
+            log::warn!("Error moving {file_path:?} to {trash_path:?}: {err}");
+            return None;
+        }
+    }
+}
+
+/// List all the paper files in the trash directory of `folderdir`, sorted from the oldest to the
+/// most recent deletion. Files in the trash directory that were not put there by `move_to_trash`
+/// are ignored.
+pub fn list_trash(folderdir: &std::path::PathBuf) -> Vec<TrashEntry> {
+    let mut entries: Vec<TrashEntry> = Vec::new();
+    let dir = trash_dir(folderdir);
+    if !dir.exists() {
+        return entries;
+    }
+    let paths = match std::fs::read_dir(&dir) {
+        Ok(p) => p,
+        Err(err) => {
+            log::warn!("Error obtaining paths to files in {dir:?}: {err}");
+            return entries;
+        }
+    };
+    for path in paths {
+        let file_path = match path {
+            Ok(p) => p.path(),
+            Err(err) => {
+                log::warn!("Error extracting `DirEntry` from a path, skipping this path: {err}");
+                continue;
+            }
+        };
+        let file_name = match file_path.file_name().and_then(|f| f.to_str()) {
+            Some(f) => f.to_string(),
+            None => continue,
+        };
+        // Split the file name into the time stamp and the original name.
+        let (timestamp, original_name) = match file_name.split_once('_') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        // Leave out the counter `move_to_trash` adds to repeated deletions.
+        let timestamp = match timestamp.split_once('.') {
+            Some((t, _)) => t,
+            None => timestamp,
+        };
+        let deleted = match chrono::NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT) {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+        let title = match parse_paper_toml(&file_path) {
+            Some(p) => p.title,
+            None => String::new(),
+        };
+        entries.push(TrashEntry {
+            path: file_path.clone(),
+            original_name: original_name.to_string(),
+            deleted,
+            title,
+        });
+    }
+    entries.sort_by(|a, b| {
+        a.deleted
+            .cmp(&b.deleted)
+            .then(deletion_counter(&a.path).cmp(&deletion_counter(&b.path)))
+            .then(a.path.cmp(&b.path))
+    });
+    return entries;
+}
+
+/// Get the counter `move_to_trash` adds to the time stamp of the file at `trash_path` when the
+/// same file was deleted more than once within a second, or 1 if there is none.
+fn deletion_counter(trash_path: &std::path::PathBuf) -> u32 {
+    return trash_path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .and_then(|f| f.split_once('_'))
+        .and_then(|(timestamp, _)| timestamp.split_once('.'))
+        .and_then(|(_, counter)| counter.parse::<u32>().ok())
+        .unwrap_or(1);
+}
+
+/// Move the paper file at `trash_path` in the trash back to `folderdir` under its original name.
+/// Nothing happens if a paper file with that name already exists. The restored path is returned,
+/// or `None` if the file could not be restored.
+pub fn restore_from_trash(
+    trash_path: &std::path::PathBuf,
+    folderdir: &std::path::PathBuf,
+) -> Option<std::path::PathBuf> {
+    let original_name = match trash_path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .and_then(|f| f.split_once('_'))
+    {
+        Some((_, name)) => name.to_string(),
+        None => {
+            log::warn!("{trash_path:?} is not a file in the trash. Stop restoring it.");
+            return None;
+        }
+    };
+    let mut restored_path = folderdir.clone();
+    restored_path.push(original_name);
+    if restored_path.exists() {
+        log::warn!("{restored_path:?} already exists. Stop restoring {trash_path:?}.");
+        return None;
+    }
+    match std::fs::rename(trash_path, &restored_path) {
+        Ok(_) => return Some(restored_path),
+        Err(err) => {
+            log::warn!("Error moving {trash_path:?} to {restored_path:?}: {err}");
+            return None;
+        }
+    }
+}
+
+/// Permanently delete all the paper files in the trash of `folderdir` that were deleted at least
+/// `days` days ago. The amount of permanently deleted files is returned.
+pub fn purge_trash(folderdir: &std::path::PathBuf, days: u32) -> usize {
+    let threshold = chrono::Local::now().naive_local() - chrono::Duration::days(days as i64);
+    let mut purged: usize = 0;
+    for entry in list_trash(folderdir) {
+        if entry.deleted > threshold {
+            continue;
+        }
+        match std::fs::remove_file(&entry.path) {
+            Ok(_) => purged += 1,
+            Err(err) => {
+                log::warn!("Error removing {:?} from the trash: {err}", entry.path);
+            }
+        }
+    }
+    return purged;
+}
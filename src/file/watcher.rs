@@ -0,0 +1,65 @@
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::sync::mpsc::{channel, Receiver};
+
+/// Watches the directory with the paper files for changes, using the native file system
+/// notifications of the OS (inotify on Linux). The `receiver` field receives the events of the
+/// `watcher` field, which needs to be kept alive for as long as the directory is watched.
+pub struct LibraryWatcher {
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<notify::Event>>,
+}
+
+impl LibraryWatcher {
+    /// Start watching the paper files in `folderdir`. Returns `None` if the directory could not
+    /// be watched, in which case the TUI simply won't reload on changes.
+    pub fn new(folderdir: &std::path::PathBuf) -> Option<Self> {
+        let (sender, receiver) = channel();
+        let mut watcher = match notify::recommended_watcher(sender) {
+            Ok(w) => w,
+            Err(err) => {
+                log::warn!("Error creating file system watcher, changes on disk will not be reloaded: {err}");
+                return None;
+            }
+        };
+        match watcher.watch(folderdir, RecursiveMode::NonRecursive) {
+            Ok(_) => {}
+            Err(err) => {
+                log::warn!(
+                    "Error watching {folderdir:?}, changes on disk will not be reloaded: {err}"
+                );
+                return None;
+            }
+        }
+        return Some(LibraryWatcher {
+            _watcher: watcher,
+            receiver,
+        });
+    }
+
+    /// Check, without blocking, if any paper file was created, modified, renamed or removed since
+    /// the last call. Changes to other files in the directory, such as the log file, are ignored.
+    pub fn has_changes(&self) -> bool {
+        let mut changed = false;
+        // Drain all pending events, so a burst of events results in a single reload.
+        for event in self.receiver.try_iter() {
+            let event = match event {
+                Ok(e) => e,
+                Err(err) => {
+                    log::warn!("Error receiving file system event: {err}");
+                    continue;
+                }
+            };
+            if event.kind.is_access() {
+                continue;
+            }
+            if event
+                .paths
+                .iter()
+                .any(|p| p.extension().map_or(false, |e| e == "toml"))
+            {
+                changed = true;
+            }
+        }
+        return changed;
+    }
+}
@@ -0,0 +1,345 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Names of the special keys that can be used between angle brackets, e.g. `<Down>`, together
+/// with the `KeyCode` they stand for.
+const SPECIAL_KEYS: [(&str, KeyCode); 16] = [
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Enter", KeyCode::Enter),
+    ("CR", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Tab", KeyCode::Tab),
+    ("BS", KeyCode::Backspace),
+    ("Backspace", KeyCode::Backspace),
+    ("Del", KeyCode::Delete),
+    ("Space", KeyCode::Char(' ')),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+];
+
+/// A single key press, e.g. `j`, `<C-d>` or `<Down>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Create a `KeyChord` from a key event read from the terminal. The shift modifier is
+    /// dropped for characters, since it is already part of the character, e.g. `G`.
+    pub fn from_event(event: &KeyEvent) -> Self {
+        let mut modifiers = event.modifiers;
+        if let KeyCode::Char(_) = event.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        KeyChord {
+            code: event.code,
+            modifiers,
+        }
+    }
+
+    /// Parse the inside of an angle bracket key specification, e.g. `C-d` from `<C-d>`. The
+    /// modifiers `C` (control), `A` or `M` (alt) and `S` (shift) can be combined, e.g. `C-A-x`.
+    fn parse_special(spec: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut name = spec;
+        // Strip the modifier prefixes. A lone `-` is a key in its own right.
+        while name.len() > 2 && name.as_bytes()[1] == b'-' {
+            match &name[..1] {
+                "C" | "c" => modifiers.insert(KeyModifiers::CONTROL),
+                "A" | "a" | "M" | "m" => modifiers.insert(KeyModifiers::ALT),
+                "S" | "s" => modifiers.insert(KeyModifiers::SHIFT),
+                _ => return None,
+            }
+            name = &name[2..];
+        }
+        let mut code = if name.chars().count() == 1 {
+            KeyCode::Char(name.chars().next()?)
+        } else if name.eq_ignore_ascii_case("lt") {
+            KeyCode::Char('<')
+        } else if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+            KeyCode::F(n)
+        } else {
+            SPECIAL_KEYS
+                .iter()
+                .find(|(special, _)| special.eq_ignore_ascii_case(name))
+                .map(|(_, code)| *code)?
+        };
+        // Shift is part of the character itself, and shift-tab is reported as back-tab.
+        if modifiers.contains(KeyModifiers::SHIFT) {
+            match code {
+                KeyCode::Char(c) => {
+                    code = KeyCode::Char(c.to_ascii_uppercase());
+                    modifiers.remove(KeyModifiers::SHIFT);
+                }
+                KeyCode::Tab => {
+                    code = KeyCode::BackTab;
+                    modifiers.remove(KeyModifiers::SHIFT);
+                }
+                _ => {}
+            }
+        }
+        return Some(KeyChord { code, modifiers });
+    }
+}
+
+impl std::fmt::Display for KeyChord {
+    /// Display the `KeyChord` in the same notation it is parsed from.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self.code {
+            KeyCode::Char(' ') => String::from("Space"),
+            KeyCode::Char('<') => String::from("lt"),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::BackTab => String::from("S-Tab"),
+            KeyCode::F(n) => format!("F{}", n),
+            code => match SPECIAL_KEYS.iter().find(|(_, c)| *c == code) {
+                Some((special, _)) => special.to_string(),
+                None => format!("{:?}", code),
+            },
+        };
+        let mut prefix = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            prefix.push_str("C-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            prefix.push_str("A-");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            prefix.push_str("S-");
+        }
+        if prefix.is_empty() && name.chars().count() == 1 {
+            return write!(f, "{}", name);
+        }
+        return write!(f, "<{}{}>", prefix, name);
+    }
+}
+
+/// A sequence of key presses bound to an action, e.g. `gg`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<KeyChord>);
+
+impl KeySequence {
+    /// Parse a key specification such as `j`, `gg`, `<C-d>`, `<Down>` or `g<Enter>`. Characters
+    /// outside angle brackets are keys as they are typed. Returns `None` if the specification is
+    /// empty or contains an unknown key.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut chords: Vec<KeyChord> = Vec::new();
+        let mut rest = spec;
+        while let Some(c) = rest.chars().next() {
+            // A `<` starts a special key, if there is a matching `>`.
+            if c == '<' {
+                if let Some(end) = rest.find('>') {
+                    if end > 1 {
+                        chords.push(KeyChord::parse_special(&rest[1..end])?);
+                        rest = &rest[end + 1..];
+                        continue;
+                    }
+                }
+            }
+            chords.push(KeyChord {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE,
+            });
+            rest = &rest[c.len_utf8()..];
+        }
+        if chords.is_empty() {
+            return None;
+        }
+        return Some(KeySequence(chords));
+    }
+}
+
+impl std::fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for chord in &self.0 {
+            write!(f, "{}", chord)?;
+        }
+        return Ok(());
+    }
+}
+
+/// All the key sequences bound to a single action. In the configuration file this is either a
+/// single key specification, e.g. `"j"`, or a list of them, e.g. `["j", "<Down>"]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyBindings(pub Vec<KeySequence>);
+
+impl KeyBindings {
+    /// Create `KeyBindings` from a list of key specifications, see `KeySequence::parse`. Invalid
+    /// specifications are skipped with a warning.
+    pub fn from_specs(specs: &[&str]) -> Self {
+        let mut sequences: Vec<KeySequence> = Vec::new();
+        for spec in specs {
+            match KeySequence::parse(spec) {
+                Some(s) => sequences.push(s),
+                None => log::warn!("Invalid key specification {spec:?} is ignored."),
+            }
+        }
+        return KeyBindings(sequences);
+    }
+
+    /// The first bound key sequence as a string, used where there is only room to show one.
+    pub fn primary(&self) -> String {
+        match self.0.first() {
+            Some(s) => return s.to_string(),
+            None => return String::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for KeyBindings {
+    /// Display all the bound key sequences, separated by commas.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sequences: Vec<String> = self.0.iter().map(|s| s.to_string()).collect();
+        return write!(f, "{}", sequences.join(", "));
+    }
+}
+
+/// Helper for deserializing either a single key specification or a list of them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl<'de> Deserialize<'de> for KeyBindings {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let specs = match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(s) => vec![s],
+            OneOrMany::Many(v) => v,
+        };
+        let mut sequences: Vec<KeySequence> = Vec::new();
+        for spec in specs {
+            match KeySequence::parse(&spec) {
+                Some(s) => sequences.push(s),
+                None => {
+                    return Err(serde::de::Error::custom(format!(
+                        "invalid key specification {:?}",
+                        spec
+                    )))
+                }
+            }
+        }
+        return Ok(KeyBindings(sequences));
+    }
+}
+
+impl Serialize for KeyBindings {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let specs: Vec<String> = self.0.iter().map(|s| s.to_string()).collect();
+        return specs.serialize(serializer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        return KeyChord { code, modifiers };
+    }
+
+    fn chars(keys: &str) -> Vec<KeyChord> {
+        return keys
+            .chars()
+            .map(|c| chord(KeyCode::Char(c), KeyModifiers::NONE))
+            .collect();
+    }
+
+    #[test]
+    fn parse_sequences() {
+        assert_eq!(KeySequence::parse("j"), Some(KeySequence(chars("j"))));
+        assert_eq!(KeySequence::parse("gg"), Some(KeySequence(chars("gg"))));
+        // A space outside angle brackets is a key of its own.
+        assert_eq!(KeySequence::parse("g g"), Some(KeySequence(chars("g g"))));
+        assert_eq!(KeySequence::parse("g g"), KeySequence::parse("g<Space>g"));
+        assert_eq!(
+            KeySequence::parse("g<Enter>"),
+            Some(KeySequence(vec![
+                chord(KeyCode::Char('g'), KeyModifiers::NONE),
+                chord(KeyCode::Enter, KeyModifiers::NONE),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_modifiers() {
+        assert_eq!(
+            KeySequence::parse("<C-d>"),
+            Some(KeySequence(vec![chord(
+                KeyCode::Char('d'),
+                KeyModifiers::CONTROL
+            )]))
+        );
+        assert_eq!(
+            KeySequence::parse("<C-A-x>"),
+            Some(KeySequence(vec![chord(
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )]))
+        );
+        assert_eq!(KeySequence::parse("<M-x>"), KeySequence::parse("<A-x>"));
+        // Shift is part of the character, and shift-tab is back-tab.
+        assert_eq!(KeySequence::parse("<S-g>"), KeySequence::parse("G"));
+        assert_eq!(
+            KeySequence::parse("<S-Tab>"),
+            Some(KeySequence(vec![chord(
+                KeyCode::BackTab,
+                KeyModifiers::NONE
+            )]))
+        );
+        assert_eq!(
+            KeySequence::parse("<C-->"),
+            Some(KeySequence(vec![chord(
+                KeyCode::Char('-'),
+                KeyModifiers::CONTROL
+            )]))
+        );
+    }
+
+    #[test]
+    fn parse_special_keys() {
+        assert_eq!(KeySequence::parse("<lt>"), Some(KeySequence(chars("<"))));
+        assert_eq!(KeySequence::parse("<CR>"), KeySequence::parse("<enter>"));
+        assert_eq!(
+            KeySequence::parse("<F5>"),
+            Some(KeySequence(vec![chord(KeyCode::F(5), KeyModifiers::NONE)]))
+        );
+        // Angle brackets without a key name inside are keys themselves.
+        assert_eq!(KeySequence::parse("<>"), Some(KeySequence(chars("<>"))));
+        assert_eq!(KeySequence::parse("a<"), Some(KeySequence(chars("a<"))));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(KeySequence::parse(""), None);
+        assert_eq!(KeySequence::parse("<Foo>"), None);
+        assert_eq!(KeySequence::parse("<X-a>"), None);
+        assert_eq!(KeySequence::parse("g<C-Nope>"), None);
+        assert!(toml::from_str::<KeyBindings>("\"<Foo>\"").is_err());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for spec in [
+            "j", "gg", "<C-d>", "<C-A-x>", "<Space>", "<lt>", "<S-Tab>", "<F12>", "g<CR>",
+        ] {
+            let sequence = KeySequence::parse(spec).unwrap();
+            assert_eq!(KeySequence::parse(&sequence.to_string()), Some(sequence));
+        }
+        let bindings = KeyBindings::from_specs(&["<C-f>", "<PageDown>", "J", "<Nope>"]);
+        assert_eq!(bindings.to_string(), "<C-f>, <PageDown>, J");
+        assert_eq!(bindings.primary(), "<C-f>");
+    }
+}
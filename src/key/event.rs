@@ -1,66 +1,404 @@
+use super::binding::{KeyBindings, KeyChord};
 use crate::configuration::config::Config;
+use std::time::{Duration, Instant};
 
 /// Contains all the possible events that can happen while the TUI is
-/// open.
+/// open. Mouse events carry the column and row of the mouse cursor.
+/// `Cancel` is sent when `Esc` is pressed and not bound to an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum KeyEvents {
     NoEvent,
     Next,
     Previous,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    GoToTop,
+    GoToBottom,
+    GoToIndex,
     Bibtex,
     Edit,
+    EditField,
+    EditTags,
     Delete,
+    UndoDelete,
     Open,
+    ToggleMark,
+    VisualMark,
+    ClearMarks,
+    SetStatus,
+    CopyCitationKeys,
+    ExportBibtex,
+    TagBrowser,
+    CollapseTag,
+    ExpandTag,
+    ToggleBibtex,
+    SwitchLibrary,
+    Help,
+    Cancel,
+    Click(u16, u16),
+    ScrollUp(u16, u16),
+    ScrollDown(u16, u16),
     Quit,
 }
 
-/// Reads the user input, and if it matches with the key binds as
-/// dictated by the configuration file, or by the Default implementation
-/// of KeybindsFromFile.
-pub fn get_key_event(config: &Config) -> KeyEvents {
-    match crossterm::event::poll(std::time::Duration::from_millis(50)) {
-        Ok(_) => {
-            let event_read = match crossterm::event::read() {
-                Ok(event) => event,
-                Err(err) => {
-                    log::warn!("Error reading events, `NoEvent` is returned: {err}");
-                    return KeyEvents::NoEvent;
+/// List all the actions that can be bound to keys in the configuration file, with the name of
+/// the action in the configuration file and the keys currently bound to it.
+pub fn bound_actions(config: &Config) -> Vec<(KeyEvents, &'static str, &KeyBindings)> {
+    let k = &config.keybinds;
+    return vec![
+        (KeyEvents::Quit, "quit", &k.quit),
+        (KeyEvents::Next, "next", &k.next),
+        (KeyEvents::Previous, "previous", &k.previous),
+        (KeyEvents::PageDown, "page_down", &k.page_down),
+        (KeyEvents::PageUp, "page_up", &k.page_up),
+        (KeyEvents::HalfPageDown, "half_page_down", &k.half_page_down),
+        (KeyEvents::HalfPageUp, "half_page_up", &k.half_page_up),
+        (KeyEvents::GoToTop, "go_to_top", &k.go_to_top),
+        (KeyEvents::GoToBottom, "go_to_bottom", &k.go_to_bottom),
+        (KeyEvents::GoToIndex, "go_to_index", &k.go_to_index),
+        (
+            KeyEvents::Bibtex,
+            "bibtex_to_clipboard",
+            &k.bibtex_to_clipboard,
+        ),
+        (KeyEvents::Edit, "edit", &k.edit),
+        (KeyEvents::EditField, "edit_field", &k.edit_field),
+        (KeyEvents::EditTags, "edit_tags", &k.edit_tags),
+        (KeyEvents::Delete, "delete", &k.delete),
+        (KeyEvents::UndoDelete, "undo_delete", &k.undo_delete),
+        (KeyEvents::Open, "open_in_pdfviewer", &k.open_in_pdfviewer),
+        (KeyEvents::ToggleMark, "toggle_mark", &k.toggle_mark),
+        (KeyEvents::VisualMark, "visual_mark", &k.visual_mark),
+        (KeyEvents::ClearMarks, "clear_marks", &k.clear_marks),
+        (KeyEvents::SetStatus, "set_status", &k.set_status),
+        (
+            KeyEvents::CopyCitationKeys,
+            "copy_citation_keys",
+            &k.copy_citation_keys,
+        ),
+        (KeyEvents::ExportBibtex, "export_bibtex", &k.export_bibtex),
+        (KeyEvents::TagBrowser, "tag_browser", &k.tag_browser),
+        (KeyEvents::CollapseTag, "collapse_tag", &k.collapse_tag),
+        (KeyEvents::ExpandTag, "expand_tag", &k.expand_tag),
+        (KeyEvents::ToggleBibtex, "toggle_bibtex", &k.toggle_bibtex),
+        (
+            KeyEvents::SwitchLibrary,
+            "switch_library",
+            &k.switch_library,
+        ),
+        (KeyEvents::Help, "help", &k.help),
+    ];
+}
+
+impl KeyEvents {
+    /// Short description of the action, as shown in the help overlay.
+    pub fn description(&self) -> &'static str {
+        match self {
+            KeyEvents::Next => return "Select the next paper",
+            KeyEvents::Previous => return "Select the previous paper",
+            KeyEvents::PageDown => return "Move down a page",
+            KeyEvents::PageUp => return "Move up a page",
+            KeyEvents::HalfPageDown => return "Move down half a page",
+            KeyEvents::HalfPageUp => return "Move up half a page",
+            KeyEvents::GoToTop => return "Select the first paper",
+            KeyEvents::GoToBottom => return "Select the last paper",
+            KeyEvents::GoToIndex => return "Select a paper by its number",
+            KeyEvents::Bibtex => return "Copy the bibtex to the clipboard",
+            KeyEvents::Edit => return "Open the paper file in the editor",
+            KeyEvents::EditField => return "Edit a single field of the paper",
+            KeyEvents::EditTags => return "Add or remove tags",
+            KeyEvents::Delete => return "Move the paper(s) to the trash",
+            KeyEvents::UndoDelete => return "Restore the last deleted paper(s)",
+            KeyEvents::Open => return "Open the PDF in the PDF viewer",
+            KeyEvents::ToggleMark => return "Mark or unmark the paper",
+            KeyEvents::VisualMark => return "Start or mark a visual range",
+            KeyEvents::ClearMarks => return "Unmark all papers",
+            KeyEvents::SetStatus => return "Set the reading status",
+            KeyEvents::CopyCitationKeys => return "Copy the citation key(s) to the clipboard",
+            KeyEvents::ExportBibtex => return "Export the bibtex to a file",
+            KeyEvents::TagBrowser => return "Browse the tags to filter the papers",
+            KeyEvents::CollapseTag => return "Hide the child tags, or go to the parent tag",
+            KeyEvents::ExpandTag => return "Show the child tags",
+            KeyEvents::ToggleBibtex => return "Show or hide the bibtex",
+            KeyEvents::SwitchLibrary => return "Switch to another library",
+            KeyEvents::Help => return "Show or close this help",
+            KeyEvents::Cancel => return "Cancel a key sequence or close the help",
+            KeyEvents::Click(_, _) => return "Select the clicked paper",
+            KeyEvents::ScrollUp(_, _) | KeyEvents::ScrollDown(_, _) => {
+                return "Scroll the papers or the description"
+            }
+            KeyEvents::Quit => return "Exit the TUI",
+            KeyEvents::NoEvent => return "",
+        }
+    }
+}
+
+/// Result of matching the keys pressed so far against the bound key sequences.
+#[derive(Debug, PartialEq, Eq)]
+enum SequenceMatch {
+    /// No bound sequence starts with the pressed keys.
+    None,
+    /// The pressed keys are the start of at least one longer bound sequence. If they are also a
+    /// complete sequence themselves, that action is included.
+    Partial(Option<KeyEvents>),
+    /// The pressed keys form a complete sequence, and no longer sequence starts with them.
+    Complete(KeyEvents),
+}
+
+/// Turns key presses into `KeyEvents`, keeping track of the keys of a multi-key sequence such as
+/// `gg` that were pressed so far. If the next key of a sequence is not pressed within the
+/// `key_sequence_timeout` of the configuration, the sequence is abandoned, or the action bound to
+/// the keys pressed so far is performed if there is one.
+pub struct KeyDispatcher {
+    pending: Vec<KeyChord>,
+    last_press: Instant,
+}
+
+impl Default for KeyDispatcher {
+    fn default() -> Self {
+        KeyDispatcher {
+            pending: Vec::new(),
+            last_press: Instant::now(),
+        }
+    }
+}
+
+impl KeyDispatcher {
+    /// Reads the user input, and if it matches with the key binds as
+    /// dictated by the configuration file, or by the Default implementation
+    /// of KeybindsFromFile, return the corresponding event.
+    pub fn get_key_event(&mut self, config: &Config) -> KeyEvents {
+        if let Some(action) = self.expire(config) {
+            return action;
+        }
+        match crossterm::event::poll(std::time::Duration::from_millis(50)) {
+            Ok(true) => {}
+            Ok(false) => return KeyEvents::NoEvent,
+            Err(err) => {
+                log::warn!("Error polling event, `NoEvent` is returned: {err}");
+                return KeyEvents::NoEvent;
+            }
+        }
+        let event_read = match crossterm::event::read() {
+            Ok(event) => event,
+            Err(err) => {
+                log::warn!("Error reading events, `NoEvent` is returned: {err}");
+                return KeyEvents::NoEvent;
+            }
+        };
+
+        if let crossterm::event::Event::Mouse(mouse) = event_read {
+            match mouse.kind {
+                crossterm::event::MouseEventKind::Down(crossterm::event::MouseButton::Left) => {
+                    return KeyEvents::Click(mouse.column, mouse.row)
+                }
+                crossterm::event::MouseEventKind::ScrollUp => {
+                    return KeyEvents::ScrollUp(mouse.column, mouse.row)
+                }
+                crossterm::event::MouseEventKind::ScrollDown => {
+                    return KeyEvents::ScrollDown(mouse.column, mouse.row)
                 }
-            };
-
-            if let crossterm::event::Event::Key(key) = event_read {
-                if key.kind == crossterm::event::KeyEventKind::Press {
-                    if key.code == crossterm::event::KeyCode::Char(config.keybinds.quit) {
-                        return KeyEvents::Quit;
-                    } else if key.code == crossterm::event::KeyCode::Char(config.keybinds.next) {
-                        return KeyEvents::Next;
-                    } else if key.code == crossterm::event::KeyCode::Char(config.keybinds.previous)
-                    {
-                        return KeyEvents::Previous;
-                    } else if key.code
-                        == crossterm::event::KeyCode::Char(config.keybinds.bibtex_to_clipboard)
-                    {
-                        return KeyEvents::Bibtex;
-                    } else if key.code == crossterm::event::KeyCode::Char(config.keybinds.edit) {
-                        return KeyEvents::Edit;
-                    } else if key.code
-                        == crossterm::event::KeyCode::Char(config.keybinds.open_in_pdfviewer)
-                    {
-                        return KeyEvents::Open;
-                    } else if key.code == crossterm::event::KeyCode::Char(config.keybinds.delete) {
-                        return KeyEvents::Delete;
-                    } else {
+                _ => return KeyEvents::NoEvent,
+            }
+        }
+        let key = match event_read {
+            crossterm::event::Event::Key(key) => key,
+            _ => return KeyEvents::NoEvent,
+        };
+        if key.kind != crossterm::event::KeyEventKind::Press {
+            return KeyEvents::NoEvent;
+        }
+        return self.press(config, KeyChord::from_event(&key));
+    }
+
+    /// Resolve a pending sequence that was not continued within the `key_sequence_timeout`.
+    /// The action bound to the keys pressed so far is returned if there is one.
+    fn expire(&mut self, config: &Config) -> Option<KeyEvents> {
+        let timeout = Duration::from_millis(config.general.key_sequence_timeout);
+        if !self.pending.is_empty() && self.last_press.elapsed() >= timeout {
+            let pending: Vec<KeyChord> = self.pending.drain(..).collect();
+            if let SequenceMatch::Partial(Some(action)) = match_sequence(config, &pending) {
+                return Some(action);
+            }
+        }
+        return None;
+    }
+
+    /// Add the pressed `chord` to the pending sequence and return the action it completes, or
+    /// `NoEvent` while waiting for the next key of a sequence.
+    fn press(&mut self, config: &Config, chord: KeyChord) -> KeyEvents {
+        self.pending.push(chord);
+        self.last_press = Instant::now();
+        match match_sequence(config, &self.pending) {
+            SequenceMatch::Complete(action) => {
+                self.pending.clear();
+                return action;
+            }
+            SequenceMatch::Partial(_) => {
+                // Wait for the next key of the sequence.
+                return KeyEvents::NoEvent;
+            }
+            SequenceMatch::None => {
+                // The sequence is broken, but the last key may start a new one.
+                let last = self.pending.split_off(self.pending.len() - 1);
+                self.pending.clear();
+                match match_sequence(config, &last) {
+                    SequenceMatch::Complete(action) => return action,
+                    SequenceMatch::Partial(_) => {
+                        self.pending = last;
+                        return KeyEvents::NoEvent;
+                    }
+                    SequenceMatch::None => {
+                        // An unbound escape cancels whatever is going on.
+                        if chord.code == crossterm::event::KeyCode::Esc {
+                            return KeyEvents::Cancel;
+                        }
                         return KeyEvents::NoEvent;
                     }
-                } else {
-                    return KeyEvents::NoEvent;
                 }
-            } else {
-                return KeyEvents::NoEvent;
             }
         }
-        Err(err) => {
-            log::warn!("Error polling event, `NoEvent` is returned: {err}");
-            return KeyEvents::NoEvent;
+    }
+}
+
+/// Match the `pressed` keys against all the bound key sequences, see `SequenceMatch`.
+fn match_sequence(config: &Config, pressed: &[KeyChord]) -> SequenceMatch {
+    let mut complete: Option<KeyEvents> = None;
+    let mut longer = false;
+    for (action, _, bindings) in bound_actions(config) {
+        for sequence in &bindings.0 {
+            if sequence.0 == pressed {
+                complete = complete.or(Some(action));
+            } else if sequence.0.starts_with(pressed) {
+                longer = true;
+            }
         }
-    };
+    }
+    match (complete, longer) {
+        (Some(action), false) => return SequenceMatch::Complete(action),
+        (action, true) => return SequenceMatch::Partial(action),
+        (None, false) => return SequenceMatch::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    /// Default configuration, as used without a configuration file.
+    fn config() -> Config {
+        return Config::from_config_file(&std::path::PathBuf::from("/nonexistent/dumpling.toml"));
+    }
+
+    fn keys(spec: &str) -> Vec<KeyChord> {
+        return KeyBindings::from_specs(&[spec]).0[0].0.clone();
+    }
+
+    fn press_all(dispatcher: &mut KeyDispatcher, config: &Config, spec: &str) -> Vec<KeyEvents> {
+        return keys(spec)
+            .into_iter()
+            .map(|chord| dispatcher.press(config, chord))
+            .collect();
+    }
+
+    #[test]
+    fn match_prefix_and_full_sequences() {
+        let config = config();
+        assert_eq!(
+            match_sequence(&config, &keys("j")),
+            SequenceMatch::Complete(KeyEvents::Next)
+        );
+        assert_eq!(
+            match_sequence(&config, &keys("g")),
+            SequenceMatch::Partial(None)
+        );
+        assert_eq!(
+            match_sequence(&config, &keys("gg")),
+            SequenceMatch::Complete(KeyEvents::GoToTop)
+        );
+        assert_eq!(
+            match_sequence(&config, &keys("<C-d>")),
+            SequenceMatch::Complete(KeyEvents::HalfPageDown)
+        );
+        assert_eq!(match_sequence(&config, &keys("z")), SequenceMatch::None);
+        assert_eq!(match_sequence(&config, &keys("ggg")), SequenceMatch::None);
+    }
+
+    #[test]
+    fn match_sequence_that_is_also_a_prefix() {
+        let mut config = config();
+        config.keybinds.quit = KeyBindings::from_specs(&["g"]);
+        assert_eq!(
+            match_sequence(&config, &keys("g")),
+            SequenceMatch::Partial(Some(KeyEvents::Quit))
+        );
+        assert_eq!(
+            match_sequence(&config, &keys("gg")),
+            SequenceMatch::Complete(KeyEvents::GoToTop)
+        );
+    }
+
+    #[test]
+    fn dispatch_sequences() {
+        let config = config();
+        let mut dispatcher = KeyDispatcher::default();
+        assert_eq!(
+            press_all(&mut dispatcher, &config, "gg"),
+            vec![KeyEvents::NoEvent, KeyEvents::GoToTop]
+        );
+        // A broken sequence drops the pending keys, the last key can still act on its own.
+        assert_eq!(
+            press_all(&mut dispatcher, &config, "gj"),
+            vec![KeyEvents::NoEvent, KeyEvents::Next]
+        );
+        assert_eq!(
+            press_all(&mut dispatcher, &config, "zgg"),
+            vec![KeyEvents::NoEvent, KeyEvents::NoEvent, KeyEvents::GoToTop]
+        );
+        // An unbound escape cancels.
+        let esc = KeyChord {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+        };
+        assert_eq!(dispatcher.press(&config, esc), KeyEvents::Cancel);
+    }
+
+    #[test]
+    fn dispatch_timeout() {
+        let mut config = config();
+        config.keybinds.quit = KeyBindings::from_specs(&["g"]);
+        let mut dispatcher = KeyDispatcher::default();
+        // Within the timeout, the longer sequence is still possible.
+        assert_eq!(
+            press_all(&mut dispatcher, &config, "g"),
+            vec![KeyEvents::NoEvent]
+        );
+        assert_eq!(dispatcher.expire(&config), None);
+        assert_eq!(
+            press_all(&mut dispatcher, &config, "g"),
+            vec![KeyEvents::GoToTop]
+        );
+        // After the timeout, the shorter sequence is performed.
+        config.general.key_sequence_timeout = 0;
+        assert_eq!(
+            press_all(&mut dispatcher, &config, "g"),
+            vec![KeyEvents::NoEvent]
+        );
+        assert_eq!(dispatcher.expire(&config), Some(KeyEvents::Quit));
+        assert_eq!(dispatcher.expire(&config), None);
+        // A pending prefix without an action of its own is dropped.
+        config.keybinds.quit = KeyBindings::from_specs(&["q"]);
+        assert_eq!(
+            press_all(&mut dispatcher, &config, "g"),
+            vec![KeyEvents::NoEvent]
+        );
+        assert_eq!(dispatcher.expire(&config), None);
+        assert_eq!(
+            press_all(&mut dispatcher, &config, "j"),
+            vec![KeyEvents::Next]
+        );
+    }
 }
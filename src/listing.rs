@@ -1,2 +1,6 @@
+pub mod format;
+pub mod papers;
 pub mod pdfs;
+pub mod stats;
 pub mod tags;
+pub mod trash;
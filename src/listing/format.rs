@@ -0,0 +1,128 @@
+use serde_json::{Map, Value};
+
+/// Format in which the reporting commands print their output.
+///
+/// `Text` is the human readable output of each command. The other formats print one
+/// record per entry, with the same field names in every format: `Json` prints an array of
+/// objects, `Csv` and `Tsv` a header line with the field names followed by one line per record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+    Tsv,
+}
+
+impl OutputFormat {
+    /// All the output formats.
+    pub const ALL: [OutputFormat; 4] = [
+        OutputFormat::Text,
+        OutputFormat::Json,
+        OutputFormat::Csv,
+        OutputFormat::Tsv,
+    ];
+
+    /// Name of the format, as given to `--format`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+        }
+    }
+
+    /// Parse the format from its `name`, ignoring case. An empty name gives `Text`. An error
+    /// listing the valid names is returned for an unknown name.
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        let name = name.trim().to_lowercase();
+        if name.is_empty() {
+            return Ok(OutputFormat::Text);
+        }
+        match OutputFormat::ALL.iter().find(|f| f.name() == name) {
+            Some(f) => return Ok(*f),
+            None => {
+                let valid: Vec<&str> = OutputFormat::ALL.iter().map(|f| f.name()).collect();
+                return Err(format!(
+                    "Unknown format {:?}, expected one of: {}",
+                    name,
+                    valid.join(", ")
+                ));
+            }
+        }
+    }
+}
+
+/// Separator of the values of a list, e.g. the tags of a paper, inside a single CSV or TSV field.
+const LIST_SEPARATOR: &str = "; ";
+
+/// Convert a JSON `value` into the text of a single CSV or TSV field. Lists are joined with
+/// `LIST_SEPARATOR` and a missing value becomes an empty field.
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::Null => return String::new(),
+        Value::String(s) => return s.clone(),
+        Value::Array(values) => {
+            return values
+                .iter()
+                .map(field_text)
+                .collect::<Vec<String>>()
+                .join(LIST_SEPARATOR)
+        }
+        _ => return value.to_string(),
+    }
+}
+
+/// Print the `records` in the machine-readable `format`, every record holding one value for
+/// each of the `fields`, in the same order. Nothing is printed for `OutputFormat::Text`, as
+/// every command has its own text output.
+pub fn print_records(format: OutputFormat, fields: &[&str], records: &Vec<Vec<Value>>) {
+    let delimiter = match format {
+        OutputFormat::Text => return,
+        OutputFormat::Json => {
+            let objects: Vec<Value> = records
+                .iter()
+                .map(|record| {
+                    let mut object = Map::new();
+                    for (field, value) in fields.iter().zip(record.iter()) {
+                        object.insert(field.to_string(), value.clone());
+                    }
+                    Value::Object(object)
+                })
+                .collect();
+            match serde_json::to_string_pretty(&Value::Array(objects)) {
+                Ok(s) => println!("{}", s),
+                Err(err) => {
+                    log::error!("Error serializing records into JSON: {err}");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        OutputFormat::Csv => b',',
+        OutputFormat::Tsv => b'\t',
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(std::io::stdout());
+    let mut lines: Vec<Vec<String>> = vec![fields.iter().map(|f| f.to_string()).collect()];
+    for record in records {
+        lines.push(record.iter().map(field_text).collect());
+    }
+    for line in lines {
+        match writer.write_record(&line) {
+            Ok(_) => {}
+            Err(err) => {
+                log::error!("Error writing {} record: {err}", format.name());
+                std::process::exit(1);
+            }
+        }
+    }
+    match writer.flush() {
+        Ok(_) => {}
+        Err(err) => {
+            log::error!("Error writing {} output: {err}", format.name());
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use super::format::{print_records, OutputFormat};
+use crate::configuration::config::AuthorFormat;
+use crate::file::loader::{extract_citation_key, get_all_valid_filepaths};
+use crate::file::normalize::TagNormalizer;
+use crate::file::parser::{parse_paper_toml, Paper};
+
+/// Amount of characters of the paper file name used as the ID of a paper.
+const ID_LENGTH: usize = 8;
+
+/// Column of the paper listing printed by `list_papers`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    Id,
+    Citekey,
+    Year,
+    Author,
+    Title,
+    Journal,
+    Tags,
+    Docname,
+    Status,
+}
+
+/// Columns shown when none are asked for.
+pub const DEFAULT_COLUMNS: [Column; 5] = [
+    Column::Id,
+    Column::Year,
+    Column::Author,
+    Column::Title,
+    Column::Tags,
+];
+
+impl Column {
+    /// All the columns that can be shown.
+    pub const ALL: [Column; 9] = [
+        Column::Id,
+        Column::Citekey,
+        Column::Year,
+        Column::Author,
+        Column::Title,
+        Column::Journal,
+        Column::Tags,
+        Column::Docname,
+        Column::Status,
+    ];
+
+    /// Name of the column, as given to `--columns` and shown in the header.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Citekey => "citekey",
+            Column::Year => "year",
+            Column::Author => "author",
+            Column::Title => "title",
+            Column::Journal => "journal",
+            Column::Tags => "tags",
+            Column::Docname => "docname",
+            Column::Status => "status",
+        }
+    }
+
+    /// Parse the column from its name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        return Column::ALL.iter().find(|c| c.name() == name).cloned();
+    }
+
+    /// Get the value of the column for the paper stored at `path`. The ID is the start of
+    /// the file name of the paper, and the author the first author.
+    pub fn get(&self, paper: &Paper, path: &std::path::PathBuf) -> String {
+        match self {
+            Column::Id => {
+                let stem = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default();
+                return stem.chars().take(ID_LENGTH).collect();
+            }
+            Column::Citekey => match extract_citation_key(&paper.bibtex) {
+                Some(key) => return key,
+                None => return String::new(),
+            },
+            Column::Year => return paper.year.to_string(),
+            Column::Author => match paper.authors.first() {
+                Some(a) => return a.display(AuthorFormat::Full),
+                None => return String::new(),
+            },
+            Column::Title => return paper.title.clone(),
+            Column::Journal => return paper.journal.clone(),
+            Column::Tags => {
+                return paper
+                    .tags
+                    .iter()
+                    .map(|t| t.label.clone())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            }
+            Column::Docname => return paper.docname.clone(),
+            Column::Status => return paper.status.clone(),
+        }
+    }
+
+    /// Get the value of the column for the paper stored at `path` as a JSON value. This is
+    /// the text of `Column::get`, except for the year, which is a number, and the tags, which
+    /// are a list.
+    pub fn value(&self, paper: &Paper, path: &std::path::PathBuf) -> serde_json::Value {
+        match self {
+            Column::Year => return paper.year.into(),
+            Column::Tags => {
+                return paper
+                    .tags
+                    .iter()
+                    .map(|t| t.label.clone())
+                    .collect::<Vec<String>>()
+                    .into()
+            }
+            _ => return self.get(paper, path).into(),
+        }
+    }
+}
+
+/// Parse the column `names`, each of which may hold several names separated by commas, e.g.
+/// `id,year,title`. If no names are given, the `DEFAULT_COLUMNS` are used. An error listing the
+/// valid names is returned for an unknown name.
+pub fn parse_columns(names: &[String]) -> Result<Vec<Column>, String> {
+    let mut columns: Vec<Column> = Vec::new();
+    for name in names.iter().flat_map(|n| n.split(',')) {
+        if name.trim().is_empty() {
+            continue;
+        }
+        match Column::from_name(name) {
+            Some(c) => columns.push(c),
+            None => {
+                let valid: Vec<&str> = Column::ALL.iter().map(|c| c.name()).collect();
+                return Err(format!(
+                    "Unknown column {:?}, expected one of: {}",
+                    name.trim(),
+                    valid.join(", ")
+                ));
+            }
+        }
+    }
+    if columns.is_empty() {
+        columns = DEFAULT_COLUMNS.to_vec();
+    }
+    return Ok(columns);
+}
+
+/// Get the rows of the paper listing: the values of the `columns` for every paper in
+/// `folderdir` that has all the `tag_filters`, in the same order as in the TUI.
+pub fn paper_rows(
+    folderdir: &std::path::PathBuf,
+    tag_filters: &[String],
+    normalizer: &TagNormalizer,
+    columns: &[Column],
+) -> Vec<Vec<serde_json::Value>> {
+    let mut rows: Vec<Vec<serde_json::Value>> = Vec::new();
+    for path in get_all_valid_filepaths(folderdir, tag_filters, normalizer) {
+        let paper = match parse_paper_toml(&path) {
+            Some(p) => p,
+            None => continue,
+        };
+        rows.push(columns.iter().map(|c| c.value(&paper, &path)).collect());
+    }
+    return rows;
+}
+
+/// Print the `rows` as a table below a header with the names of the `columns`, every column
+/// padded to its widest value. Lists are joined with commas, and line breaks inside a value
+/// are replaced by spaces, so every paper takes up a single line.
+fn show_table(columns: &[Column], rows: &Vec<Vec<serde_json::Value>>) {
+    let header: Vec<String> = columns.iter().map(|c| c.name().to_uppercase()).collect();
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|v| cell_text(v).replace(['\n', '\r'], " "))
+                .collect()
+        })
+        .collect();
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows.iter() {
+        for (i, value) in row.iter().enumerate() {
+            widths[i] = widths[i].max(value.chars().count());
+        }
+    }
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let mut line = String::new();
+        for (i, value) in row.iter().enumerate() {
+            if i + 1 == row.len() {
+                line.push_str(value);
+            } else {
+                line.push_str(&format!("{:<width$}  ", value, width = widths[i]));
+            }
+        }
+        println!("{}", line.trim_end());
+    }
+}
+
+/// Text of a `value` in a cell of the table printed by `show_table`.
+fn cell_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => return s.clone(),
+        serde_json::Value::Array(values) => {
+            return values
+                .iter()
+                .map(cell_text)
+                .collect::<Vec<String>>()
+                .join(", ")
+        }
+        _ => return value.to_string(),
+    }
+}
+
+/// Print the papers in `folderdir` that have all the `tag_filters` with the `columns` given
+/// by name, see `parse_columns`. For `OutputFormat::Text` they are printed as a table,
+/// otherwise as records with the column names as fields. Without any papers, only the header
+/// is printed, so scripts can tell an empty result from a paper.
+pub fn list_papers(
+    folderdir: &std::path::PathBuf,
+    tag_filters: &[String],
+    normalizer: &TagNormalizer,
+    column_names: &[String],
+    format: OutputFormat,
+) {
+    let columns = match parse_columns(column_names) {
+        Ok(c) => c,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let rows = paper_rows(folderdir, tag_filters, normalizer, &columns);
+    if format != OutputFormat::Text {
+        let fields: Vec<&str> = columns.iter().map(|c| c.name()).collect();
+        print_records(format, &fields, &rows);
+        return;
+    }
+    show_table(&columns, &rows);
+}
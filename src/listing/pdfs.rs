@@ -1,3 +1,4 @@
+use super::format::{print_records, OutputFormat};
 use crate::file::loader::expand_filepath;
 use crate::file::parser::{parse_paper_toml, Paper};
 use std::collections::HashSet;
@@ -164,7 +165,27 @@ fn show_invalid_and_not_used_paths(
     }
 }
 
-pub fn pdf_diagnostic(folderdir: &std::path::PathBuf, pdf_dir: &String) {
+/// Print the invalid and not used PDF file paths as records in the machine-readable `format`,
+/// with the fields `status`, which is `missing` for an invalid path and `unused` for a path
+/// that is not used, and `path`.
+fn print_invalid_and_not_used_paths(
+    invalid_paths: &Vec<std::path::PathBuf>,
+    not_used_paths: &Vec<std::path::PathBuf>,
+    format: OutputFormat,
+) {
+    let mut records: Vec<Vec<serde_json::Value>> = Vec::new();
+    for path in invalid_paths {
+        records.push(vec!["missing".into(), path.display().to_string().into()]);
+    }
+    for path in not_used_paths {
+        records.push(vec!["unused".into(), path.display().to_string().into()]);
+    }
+    print_records(format, &["status", "path"], &records);
+}
+
+/// Show the PDF files mentioned by the paper files in `folderdir` that do not exist in
+/// `pdf_dir`, and the PDF files in `pdf_dir` that no paper file mentions, in the given `format`.
+pub fn pdf_diagnostic(folderdir: &std::path::PathBuf, pdf_dir: &String, format: OutputFormat) {
     let pdf_dir: std::path::PathBuf = expand_filepath(&std::path::PathBuf::from(pdf_dir));
     // Load the pdf files mentioned by the paper files
     let papers = load_all_papers(folderdir);
@@ -174,5 +195,10 @@ pub fn pdf_diagnostic(folderdir: &std::path::PathBuf, pdf_dir: &String) {
     // Get the PDF file paths not mentioned by any paper file
     let not_used_paths = get_unmatched_pdf_paths(&paper_pdfs.valid_paths, &stored_pdfs);
     // Print the diagnostic
-    show_invalid_and_not_used_paths(&paper_pdfs.invalid_paths, &not_used_paths);
+    match format {
+        OutputFormat::Text => {
+            show_invalid_and_not_used_paths(&paper_pdfs.invalid_paths, &not_used_paths)
+        }
+        _ => print_invalid_and_not_used_paths(&paper_pdfs.invalid_paths, &not_used_paths, format),
+    }
 }
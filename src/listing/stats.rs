@@ -0,0 +1,240 @@
+use super::format::OutputFormat;
+use super::papers::Column;
+use super::tags::tag_tree;
+use crate::configuration::config::AuthorFormat;
+use crate::file::loader::{expand_filepath, get_all_valid_filepaths};
+use crate::file::normalize::TagNormalizer;
+use crate::file::parser::{parse_paper_toml, Paper, ADDED_FORMAT};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Amount of authors listed under the top authors.
+const TOP_AUTHORS: usize = 10;
+/// Format of the months the growth of the library is reported per.
+const MONTH_FORMAT: &str = "%Y-%m";
+
+/// Paper of the library together with the file it is stored in.
+struct StoredPaper {
+    paper: Paper,
+    path: std::path::PathBuf,
+}
+
+impl StoredPaper {
+    /// Get the month the paper was added in, from its `added` field, or from the time the
+    /// file was last modified if that field is empty or cannot be read.
+    fn added_month(&self) -> Option<String> {
+        if let Ok(date) = chrono::NaiveDate::parse_from_str(&self.paper.added, ADDED_FORMAT) {
+            return Some(date.format(MONTH_FORMAT).to_string());
+        }
+        match std::fs::metadata(&self.path).and_then(|m| m.modified()) {
+            Ok(time) => {
+                let time: chrono::DateTime<chrono::Local> = time.into();
+                return Some(time.format(MONTH_FORMAT).to_string());
+            }
+            Err(err) => {
+                log::warn!(
+                    "Could not obtain the modification time of {:?}: {err}",
+                    self.path
+                );
+                return None;
+            }
+        }
+    }
+
+    /// ID and title of the paper, as listed under the papers with something missing.
+    fn summary(&self) -> Value {
+        return json!({
+            "id": Column::Id.get(&self.paper, &self.path),
+            "title": self.paper.title,
+        });
+    }
+}
+
+/// Count how many times every key in `keys` occurs.
+fn count<I: Iterator<Item = String>>(keys: I) -> HashMap<String, usize> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for key in keys {
+        *counts.entry(key).or_insert(0) += 1;
+    }
+    return counts;
+}
+
+/// Sort the `counts` from the largest to the smallest count, and alphabetically for equal counts.
+fn by_count(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    return counts;
+}
+
+/// Gather the statistics of the `papers` as a JSON object, see the README for its fields. A
+/// paper misses its PDF if it has no `docname` or the file does not exist in `pdf_dir`.
+fn gather_stats(
+    papers: &Vec<StoredPaper>,
+    pdf_dir: &std::path::PathBuf,
+    normalizer: &TagNormalizer,
+) -> Value {
+    let mut per_year: BTreeMap<i32, usize> = BTreeMap::new();
+    for p in papers {
+        *per_year.entry(p.paper.year).or_insert(0) += 1;
+    }
+    let per_journal = by_count(count(papers.iter().map(|p| p.paper.journal.clone())));
+    let authors = by_count(count(
+        papers
+            .iter()
+            .flat_map(|p| p.paper.authors.iter())
+            .filter(|a| !a.is_others())
+            .map(|a| a.display(AuthorFormat::Full)),
+    ));
+    let plain_papers: Vec<Paper> = papers.iter().map(|p| p.paper.clone()).collect();
+    let tags = tag_tree(&plain_papers, &[], normalizer);
+
+    let missing_pdf: Vec<Value> = papers
+        .iter()
+        .filter(|p| p.paper.docname.is_empty() || !pdf_dir.join(&p.paper.docname).is_file())
+        .map(|p| p.summary())
+        .collect();
+    let missing_bibtex: Vec<Value> = papers
+        .iter()
+        .filter(|p| p.paper.bibtex.trim().is_empty())
+        .map(|p| p.summary())
+        .collect();
+
+    let mut per_month: BTreeMap<String, usize> = BTreeMap::new();
+    for p in papers {
+        if let Some(month) = p.added_month() {
+            *per_month.entry(month).or_insert(0) += 1;
+        }
+    }
+    let mut total: usize = 0;
+    let growth: Vec<Value> = per_month
+        .into_iter()
+        .map(|(month, added)| {
+            total += added;
+            json!({ "month": month, "added": added, "total": total })
+        })
+        .collect();
+
+    return json!({
+        "papers": papers.len(),
+        "per_year": per_year
+            .iter()
+            .map(|(year, count)| json!({ "year": year, "count": count }))
+            .collect::<Vec<Value>>(),
+        "per_journal": per_journal
+            .iter()
+            .map(|(journal, count)| json!({ "journal": journal, "count": count }))
+            .collect::<Vec<Value>>(),
+        "per_tag": tags
+            .iter()
+            .map(|entry| json!({ "tag": entry.path, "depth": entry.depth, "count": entry.count }))
+            .collect::<Vec<Value>>(),
+        "top_authors": authors
+            .iter()
+            .take(TOP_AUTHORS)
+            .map(|(author, count)| json!({ "author": author, "count": count }))
+            .collect::<Vec<Value>>(),
+        "missing_pdf": missing_pdf,
+        "missing_bibtex": missing_bibtex,
+        "growth": growth,
+    });
+}
+
+/// Get the entries of the list `key` of the `stats`.
+fn entries<'a>(stats: &'a Value, key: &str) -> &'a [Value] {
+    match stats[key].as_array() {
+        Some(a) => return a,
+        None => return &[],
+    }
+}
+
+/// Get the text of the `key` of a statistics entry, with `empty` for an empty string.
+fn text(entry: &Value, key: &str, empty: &str) -> String {
+    match &entry[key] {
+        Value::String(s) if s.is_empty() => return empty.to_string(),
+        Value::String(s) => return s.clone(),
+        value => return value.to_string(),
+    }
+}
+
+/// Print the `stats` gathered by `gather_stats` to the terminal as text.
+fn show_stats(stats: &Value) {
+    println!("Papers: {}", stats["papers"]);
+    println!("\nPapers per year:");
+    for entry in entries(stats, "per_year") {
+        println!("  {}: {}", entry["year"], entry["count"]);
+    }
+    println!("\nPapers per journal:");
+    for entry in entries(stats, "per_journal") {
+        println!(
+            "  {}: {}",
+            text(entry, "journal", "(no journal)"),
+            entry["count"]
+        );
+    }
+    println!("\nPapers per tag:");
+    for entry in entries(stats, "per_tag") {
+        let depth = entry["depth"].as_u64().unwrap_or(0) as usize;
+        let tag = text(entry, "tag", "");
+        let name = tag
+            .rsplit(crate::file::parser::TAG_SEPARATOR)
+            .next()
+            .unwrap_or(&tag);
+        println!("  {}{}: {}", "  ".repeat(depth), name, entry["count"]);
+    }
+    println!("\nTop authors:");
+    for entry in entries(stats, "top_authors") {
+        println!("  {}: {}", text(entry, "author", ""), entry["count"]);
+    }
+    for (key, title) in [("missing_pdf", "PDF"), ("missing_bibtex", "BibTeX")] {
+        let missing = entries(stats, key);
+        println!("\nPapers without {} ({}):", title, missing.len());
+        for entry in missing {
+            println!("  {}  {}", text(entry, "id", ""), text(entry, "title", ""));
+        }
+    }
+    println!("\nPapers added per month:");
+    for entry in entries(stats, "growth") {
+        println!(
+            "  {}: +{} (total {})",
+            text(entry, "month", ""),
+            entry["added"],
+            entry["total"]
+        );
+    }
+}
+
+/// Print statistics of the papers in `folderdir` that have all the `tag_filters`: the amount
+/// of papers per year, journal and tag, the top authors, the papers without a PDF file in
+/// `pdf_dir` or without a BibTeX entry, and the amount of papers added per month. Only the
+/// text and JSON `format`s are supported.
+pub fn library_stats(
+    folderdir: &std::path::PathBuf,
+    pdf_dir: &String,
+    tag_filters: &[String],
+    normalizer: &TagNormalizer,
+    format: OutputFormat,
+) {
+    let pdf_dir = expand_filepath(&std::path::PathBuf::from(pdf_dir));
+    let papers: Vec<StoredPaper> = get_all_valid_filepaths(folderdir, tag_filters, normalizer)
+        .into_iter()
+        .filter_map(|path| parse_paper_toml(&path).map(|paper| StoredPaper { paper, path }))
+        .collect();
+    let stats = gather_stats(&papers, &pdf_dir, normalizer);
+    match format {
+        OutputFormat::Text => show_stats(&stats),
+        OutputFormat::Json => match serde_json::to_string_pretty(&stats) {
+            Ok(s) => println!("{}", s),
+            Err(err) => {
+                log::error!("Error serializing the statistics into JSON: {err}");
+                std::process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!(
+                "The statistics can only be shown as text or json, not as {}.",
+                format.name()
+            );
+            std::process::exit(1);
+        }
+    }
+}
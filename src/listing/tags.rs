@@ -1,9 +1,12 @@
-use crate::file::parser::{parse_paper_toml, Paper};
-use std::collections::HashMap;
+use super::format::{print_records, OutputFormat};
+use crate::file::loader::get_all_valid_filepaths;
+use crate::file::normalize::TagNormalizer;
+use crate::file::parser::{overwrite_paper_keys, parse_paper_toml, Paper, Tag, TAG_SEPARATOR};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// Create a vector of all the parsed paper entries with the valid *.toml format looking only
 /// in the given `folderdir` directory.
-fn load_all_papers(folderdir: &std::path::PathBuf) -> Vec<Paper> {
+pub fn load_all_papers(folderdir: &std::path::PathBuf) -> Vec<Paper> {
     // Initialise vector
     let mut papers: Vec<Paper> = Vec::new();
     // Loop trough all files in the directory.
@@ -59,7 +62,7 @@ fn load_all_papers(folderdir: &std::path::PathBuf) -> Vec<Paper> {
 }
 
 /// Extract the tags listed in a given vector of `Paper` structs.
-fn get_tags(papers: &Vec<Paper>) -> Vec<String> {
+pub fn get_tags(papers: &Vec<Paper>) -> Vec<String> {
     // Initiate vector
     let mut tags: Vec<String> = Vec::new();
     // Loop through all the papers and get the tags listed in them.
@@ -71,33 +74,245 @@ fn get_tags(papers: &Vec<Paper>) -> Vec<String> {
     return tags;
 }
 
-/// Convert a vector of tags into a `HashMap`, which keeps only
-/// the unique entries in the given vector and keep count of
-/// how many times that entry has appeared.
-fn to_hash_map(tags: Vec<String>) -> HashMap<String, usize> {
-    // Initiate hash map
-    let mut map: HashMap<String, usize> = HashMap::new();
-    // Add each unique element in the Vec to the HashMap.
-    for tag in tags {
-        let count = map.entry(tag).or_insert(0);
-        *count += 1;
+/// Get every tag used in the papers stored in `folderdir` exactly once, sorted alphabetically.
+/// Tags that are the same according to the `normalizer` are only listed with their first
+/// spelling. This is used to offer completions when tags are added from within the TUI.
+pub fn get_unique_tags(folderdir: &std::path::PathBuf, normalizer: &TagNormalizer) -> Vec<String> {
+    let papers = load_all_papers(folderdir);
+    let mut tags: Vec<String> = Vec::new();
+    for tag in get_tags(&papers) {
+        let tag = normalizer.resolve(&tag);
+        if !tags.iter().any(|t| normalizer.same(t, &tag)) {
+            tags.push(tag);
+        }
+    }
+    tags.sort();
+    return tags;
+}
+
+/// Entry of the tag tree, in which the tags are split on `TAG_SEPARATOR` into levels.
+///
+/// The `path` field contains the full tag, e.g. `physics/qft`, and the `depth` the amount
+/// of parents it has. The `count` field contains the amount of papers that have the tag or
+/// any of its children, each paper counted once. The `has_children` field tells if there
+/// are tags below this one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagTreeEntry {
+    pub path: String,
+    pub depth: usize,
+    pub count: usize,
+    pub has_children: bool,
+}
+
+impl TagTreeEntry {
+    /// Last level of the tag, e.g. `qft` for `physics/qft`.
+    pub fn name(&self) -> &str {
+        match self.path.rsplit(TAG_SEPARATOR).next() {
+            Some(name) => return name,
+            None => return &self.path,
+        }
+    }
+}
+
+/// Build the tag tree of the tags used in `papers`, with every parent listed before its
+/// children and siblings sorted alphabetically. Parents that are not used as a tag on their
+/// own are included as well. Tags that are the same according to the `normalizer` are
+/// counted together, under their first spelling. The `extra_tags` are added with a count of
+/// zero if no paper has them, so e.g. a tag filter that matches nothing still shows up.
+pub fn tag_tree(
+    papers: &Vec<Paper>,
+    extra_tags: &[String],
+    normalizer: &TagNormalizer,
+) -> Vec<TagTreeEntry> {
+    // Count every level of every tag once per paper. Keying on the normalised levels keeps
+    // the parents in front of their children.
+    let mut counts: BTreeMap<Vec<String>, usize> = BTreeMap::new();
+    let mut spellings: HashMap<Vec<String>, String> = HashMap::new();
+    // Add every level of `tag` to `levels`, remembering the first spelling of each.
+    let mut add_levels = |tag: &str, levels: &mut HashSet<Vec<String>>| {
+        let tag = normalizer.resolve(tag);
+        let key = normalizer.key(&tag);
+        let names: Vec<&str> = tag.split(TAG_SEPARATOR).collect();
+        for depth in 1..=key.len().min(names.len()) {
+            let level_key = key[..depth].to_vec();
+            spellings
+                .entry(level_key.clone())
+                .or_insert_with(|| names[..depth].join(&TAG_SEPARATOR.to_string()));
+            levels.insert(level_key);
+        }
+    };
+    for paper in papers {
+        let mut levels_of_paper: HashSet<Vec<String>> = HashSet::new();
+        for tag in &paper.tags {
+            add_levels(&tag.label, &mut levels_of_paper);
+        }
+        for levels in levels_of_paper {
+            *counts.entry(levels).or_insert(0) += 1;
+        }
+    }
+    for tag in extra_tags {
+        let mut levels_of_tag: HashSet<Vec<String>> = HashSet::new();
+        add_levels(tag, &mut levels_of_tag);
+        for levels in levels_of_tag {
+            counts.entry(levels).or_insert(0);
+        }
+    }
+    let keys: Vec<&Vec<String>> = counts.keys().collect();
+    let mut tree: Vec<TagTreeEntry> = Vec::new();
+    for (i, (levels, count)) in counts.iter().enumerate() {
+        let has_children = match keys.get(i + 1) {
+            Some(next) => next.len() > levels.len() && next.starts_with(levels),
+            None => false,
+        };
+        tree.push(TagTreeEntry {
+            path: spellings.get(levels).cloned().unwrap_or_default(),
+            depth: levels.len() - 1,
+            count: *count,
+            has_children,
+        });
     }
-    return map;
+    return tree;
 }
 
-/// Given a `HashMap` with the present tags and how many times they
-/// appear, format the text to show to the user.
-fn show_tags_and_count(tags_hm: &HashMap<String, usize>) {
-    for (tag, count) in tags_hm {
-        println!("{}: Appears {} times", tag, count);
+/// Get the tag tree of the papers stored in `folderdir`, see `tag_tree`. This is used by
+/// the tag browser inside the TUI.
+pub fn get_tag_tree(
+    folderdir: &std::path::PathBuf,
+    extra_tags: &[String],
+    normalizer: &TagNormalizer,
+) -> Vec<TagTreeEntry> {
+    let papers = load_all_papers(folderdir);
+    return tag_tree(&papers, extra_tags, normalizer);
+}
+
+/// Print the tag tree to the terminal, each tag indented below its parent and followed
+/// by the amount of papers that have it or any of its children.
+fn show_tag_tree(tree: &Vec<TagTreeEntry>) {
+    for entry in tree {
+        println!(
+            "{}{}: Appears {} times",
+            "  ".repeat(entry.depth),
+            entry.name(),
+            entry.count
+        );
     }
 }
 
-/// Function that chains together `load_all_papers`, `get_tags`, `to_hash_map` and
-/// `show_tags_and_count`.
-pub fn list_tags(folderdir: &std::path::PathBuf) {
+/// Print the tag tree as records in the machine-readable `format`, one per tag, with the
+/// fields `tag`, `name`, `depth` and `count`.
+fn print_tag_tree(tree: &Vec<TagTreeEntry>, format: OutputFormat) {
+    let records: Vec<Vec<serde_json::Value>> = tree
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.path.clone().into(),
+                entry.name().into(),
+                entry.depth.into(),
+                entry.count.into(),
+            ]
+        })
+        .collect();
+    print_records(format, &["tag", "name", "depth", "count"], &records);
+}
+
+/// Function that chains together `load_all_papers`, `tag_tree` and `show_tag_tree`, or
+/// `print_tag_tree` if another `format` than text is asked for.
+pub fn list_tags(folderdir: &std::path::PathBuf, normalizer: &TagNormalizer, format: OutputFormat) {
     let papers = load_all_papers(folderdir);
-    let tags_vec = get_tags(&papers);
-    let tags_hm = to_hash_map(tags_vec);
-    show_tags_and_count(&tags_hm);
+    let tree = tag_tree(&papers, &[], normalizer);
+    match format {
+        OutputFormat::Text => show_tag_tree(&tree),
+        _ => print_tag_tree(&tree, format),
+    }
+}
+
+/// Get the new label of the tag `label` when the tags in `from` are renamed to `to`, or None
+/// if the tag is not affected. Tags below a renamed tag in the hierarchy move along, e.g.
+/// renaming `physics` to `phys` turns `physics/qft` into `phys/qft`.
+fn renamed_label(
+    label: &str,
+    from: &[String],
+    to: &str,
+    normalizer: &TagNormalizer,
+) -> Option<String> {
+    for old in from {
+        if let Some(rest) = normalizer.strip_parent(label, old) {
+            return Some(format!("{}{}", to, rest));
+        }
+    }
+    return None;
+}
+
+/// Rename the tags in `from` to `to` in every paper file in `folderdir`. Renaming several
+/// tags to the same tag merges them, papers that end up with a tag twice keep it once. Every
+/// affected paper is printed with its tags before and after. Tags are compared with the
+/// `normalizer`. Only the tags of the paper files are rewritten, see `overwrite_paper_keys`.
+/// If `dry_run` is set, the paper files are left untouched.
+pub fn rename_tags(
+    folderdir: &std::path::PathBuf,
+    from: &Vec<String>,
+    to: &str,
+    dry_run: bool,
+    normalizer: &TagNormalizer,
+) {
+    let to = normalizer.clean(to);
+    if to.is_empty() {
+        log::error!("The tag to rename to cannot be empty.");
+        std::process::exit(1);
+    }
+    if dry_run {
+        println!("Dry run, no paper files are changed.");
+    }
+    let mut changed: usize = 0;
+    let mut failed: usize = 0;
+    for path in get_all_valid_filepaths(folderdir, &[], normalizer) {
+        let mut paper = match parse_paper_toml(&path) {
+            Some(p) => p,
+            None => continue,
+        };
+        let old_labels: Vec<String> = paper.tags.iter().map(|t| t.label.clone()).collect();
+        let mut new_labels: Vec<String> = Vec::new();
+        for label in old_labels.iter() {
+            let new_label = match renamed_label(label, from, &to, normalizer) {
+                Some(l) => l,
+                None => label.clone(),
+            };
+            if !new_labels.iter().any(|l| normalizer.same(l, &new_label)) {
+                new_labels.push(new_label);
+            }
+        }
+        if new_labels == old_labels {
+            continue;
+        }
+        println!(
+            "{}: {} -> {}",
+            paper.title,
+            old_labels.join(" | "),
+            new_labels.join(" | ")
+        );
+        if dry_run {
+            changed += 1;
+            continue;
+        }
+        paper.tags = new_labels.into_iter().map(|label| Tag { label }).collect();
+        if overwrite_paper_keys(&paper, &path, &["tags"]) {
+            changed += 1;
+        } else {
+            failed += 1;
+            println!(
+                "Could not save {}, check the log file for more information.",
+                paper.title
+            );
+        }
+    }
+    if dry_run {
+        println!("{} paper(s) would be changed.", changed);
+    } else if failed > 0 {
+        println!(
+            "Changed {} paper(s), {} could not be saved.",
+            changed, failed
+        );
+    } else {
+        println!("Changed {} paper(s).", changed);
+    }
 }
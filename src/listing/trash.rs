@@ -0,0 +1,66 @@
+use crate::file::trash::{list_trash, purge_trash, restore_from_trash, TrashEntry};
+
+/// Find the trash entry with the given `id`. The `id` is either the number shown by `show_trash`
+/// or the file name of the entry inside the trash directory.
+fn find_entry<'a>(entries: &'a Vec<TrashEntry>, id: &str) -> Option<&'a TrashEntry> {
+    if let Ok(n) = id.trim().parse::<usize>() {
+        if n >= 1 {
+            return entries.get(n - 1);
+        }
+        return None;
+    }
+    return entries
+        .iter()
+        .find(|e| e.path.file_name().and_then(|f| f.to_str()) == Some(id.trim()));
+}
+
+/// Show all the papers in the trash of `folderdir`, each with the ID that can be used to
+/// restore it.
+pub fn show_trash(folderdir: &std::path::PathBuf) {
+    let entries = list_trash(folderdir);
+    if entries.is_empty() {
+        println!("The trash is empty.");
+        return;
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        println!(
+            "{}: {} - {} ({})",
+            i + 1,
+            entry.deleted.format("%Y-%m-%d %H:%M:%S"),
+            entry.title,
+            entry.original_name
+        );
+    }
+}
+
+/// Restore the papers with the given `ids` from the trash of `folderdir`, see `find_entry` for
+/// what an ID is. All IDs are resolved before anything is restored, so the numbering of the
+/// remaining entries does not change halfway.
+pub fn restore_trash(folderdir: &std::path::PathBuf, ids: &Vec<String>) {
+    let entries = list_trash(folderdir);
+    let mut to_restore: Vec<&TrashEntry> = Vec::new();
+    for id in ids {
+        match find_entry(&entries, id) {
+            Some(e) => to_restore.push(e),
+            None => {
+                println!("No paper with ID {} in the trash.", id);
+            }
+        }
+    }
+    for entry in to_restore {
+        match restore_from_trash(&entry.path, folderdir) {
+            Some(_) => println!("Restored: {}", entry.title),
+            None => println!(
+                "Could not restore {}, check the log file for more information.",
+                entry.title
+            ),
+        }
+    }
+}
+
+/// Permanently delete the papers in the trash of `folderdir` that were deleted at least `days`
+/// days ago, and report how many were deleted.
+pub fn purge(folderdir: &std::path::PathBuf, days: u32) {
+    let purged = purge_trash(folderdir, days);
+    println!("Permanently deleted {} paper(s) from the trash.", purged);
+}
@@ -7,76 +7,123 @@ pub mod logger;
 pub mod popup;
 pub mod ui;
 
-use args::parser::{parse_arguments, ProgArgs, NAME};
+use args::parser::{parse_arguments, ProgArgs};
 use configuration::config::Config;
-use file::loader::{compute_loader_size, Loader};
+use configuration::diagnostic::check_config;
+use file::library::Library;
+use file::loader::Loader;
+use file::normalize::TagNormalizer;
 use file::parser::write_new_paper;
+use listing::format::OutputFormat;
+use listing::papers::list_papers;
 use listing::pdfs::pdf_diagnostic;
-use listing::tags::list_tags;
+use listing::stats::library_stats;
+use listing::tags::{get_unique_tags, list_tags, rename_tags};
+use listing::trash::{purge, restore_trash, show_trash};
 use logger::logger::init_logging;
+use ui::layout::compute_loader_size;
 use ui::window::create_window;
 
 fn main() {
-    // Create cache directory if it doesn't exist.
-    let mut folderdir = match dirs::cache_dir() {
-        Some(p) => p,
-        None => {
-            std::process::exit(1);
-        }
-    };
     // Initialise logger
     init_logging();
 
-    folderdir.push(NAME);
-    if !folderdir.exists() {
-        match std::fs::create_dir(folderdir.clone()) {
-            Ok(_) => {}
-            Err(err) => {
-                log::error!("Could not create $HOME/.cache/: {err}");
-                std::process::exit(1);
-            }
-        }
-    }
+    // Get program arguments
+    let prog_args: ProgArgs = parse_arguments();
 
     // Get configuration file
-    let mut config_path = match dirs::config_dir() {
-        Some(p) => p,
-        None => {
-            log::error!("Could not obtain $HOME/.config/ as `PathBuf`");
+    let config_path = prog_args.config_path();
+    let config = Config::from_config_file(&config_path);
+
+    // Get the library and create its directory if it doesn't exist.
+    let library = match prog_args.library_spec() {
+        Some(spec) => Library::from_spec(&spec, &config),
+        None => Library::default_library(&config),
+    };
+    match library.create_dir() {
+        Ok(_) => {}
+        Err(err) => {
+            log::error!("{err}");
             std::process::exit(1);
         }
+    }
+    let folderdir = library.folderdir.clone();
+    let tag_normalizer = TagNormalizer::new(&config.tags);
+
+    // Get the tag filter, if there is one. If the tag filter is "", use None.
+    let tag_filter = {
+        if prog_args.filter_by_tag.is_empty() {
+            None
+        } else {
+            Some(prog_args.filter_by_tag.clone())
+        }
     };
-    config_path.push(NAME);
-    config_path.push(format!("{}.toml", NAME));
-    let config = Config::from_config_file(&config_path);
 
-    // Get program arguments
-    let prog_args: ProgArgs = parse_arguments();
+    // Get the output format of the listing commands.
+    let format = match OutputFormat::from_name(&prog_args.format) {
+        Ok(f) => f,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
     if prog_args.flags.open {
-        // Get the tag filter, if there is one. If the tag filter is "", use None.
-        let tag_filter = {
-            if prog_args.filter_by_tag.is_empty() {
-                None
-            } else {
-                Some(prog_args.filter_by_tag.clone())
-            }
-        };
         // Compute the size of the loader based on the terminal size.
-        let loader_size = compute_loader_size();
+        let loader_size = compute_loader_size(&config);
         // Load first section of existing papers.
-        let mut file_load: Loader = Loader::load(loader_size, &folderdir, &tag_filter);
+        let mut file_load: Loader =
+            Loader::load(loader_size, &library, &tag_filter, &tag_normalizer);
         create_window(&mut file_load, &config);
     } else {
-        if prog_args.flags.list_tags {
-            list_tags(&folderdir);
+        if prog_args.flags.check_config {
+            check_config(&config_path);
+        } else if prog_args.flags.list_tags {
+            list_tags(&folderdir, &tag_normalizer, format);
+        } else if prog_args.flags.list {
+            let tag_filters: Vec<String> = tag_filter.iter().cloned().collect();
+            list_papers(
+                &folderdir,
+                &tag_filters,
+                &tag_normalizer,
+                &prog_args.columns,
+                format,
+            );
+        } else if prog_args.flags.stats {
+            let tag_filters: Vec<String> = tag_filter.iter().cloned().collect();
+            library_stats(
+                &folderdir,
+                &library.pdf_dir,
+                &tag_filters,
+                &tag_normalizer,
+                format,
+            );
         } else if prog_args.flags.pdf_diagnostic {
-            pdf_diagnostic(&folderdir, &config.general.pdf_dir);
+            pdf_diagnostic(&folderdir, &library.pdf_dir, format);
+        } else if prog_args.flags.list_trash {
+            show_trash(&folderdir);
+        } else if !prog_args.restore.is_empty() {
+            restore_trash(&folderdir, &prog_args.restore);
+        } else if let Some(days) = prog_args.purge_trash {
+            purge(&folderdir, days);
+        } else if !prog_args.rename_tags.is_empty() {
+            rename_tags(
+                &folderdir,
+                &prog_args.rename_tags,
+                &prog_args.rename_into,
+                prog_args.flags.dry_run,
+                &tag_normalizer,
+            );
         } else {
             // If we get here, it is assumed a new entry is added. We will only add it if
             // any program arguments were set.
             match prog_args.to_paper() {
-                Some(p) => {
+                Some(mut p) => {
+                    // Store the tags with the spelling the library already uses, if any.
+                    let known_tags = get_unique_tags(&folderdir, &tag_normalizer);
+                    for tag in p.tags.iter_mut() {
+                        tag.label = tag_normalizer.canonical(&tag.label, &known_tags);
+                    }
                     write_new_paper(&p, &folderdir);
                 }
                 None => {
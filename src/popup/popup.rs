@@ -1,11 +1,12 @@
-use crate::ui::window::AppState;
-use crossterm::event::KeyCode;
+use crate::ui::window::{AppState, PopupState, RenderedAreas};
+use crossterm::event::{KeyCode, MouseButton, MouseEventKind};
 
 /// Define the internal information of a pop-up window.
 pub struct PopupCore {
     pub input: String,
     pub char_index: usize,
     pub entered_message: String,
+    pub submitted: bool,
 }
 
 impl Default for PopupCore {
@@ -15,6 +16,7 @@ impl Default for PopupCore {
             input: String::new(),
             char_index: 0,
             entered_message: String::new(),
+            submitted: false,
         }
     }
 }
@@ -26,6 +28,7 @@ impl PopupCore {
             input,
             char_index,
             entered_message,
+            submitted: false,
         }
     }
     /// Clamp the cursor position between the start of the text and the last typed character.
@@ -88,19 +91,57 @@ impl PopupCore {
             self.move_cursor_left();
         }
     }
+    /// Get the `candidates` that start with the current [`input`], ignoring case.
+    pub fn completions<'a>(&self, candidates: &'a [String]) -> Vec<&'a String> {
+        let input = self.input.to_lowercase();
+        candidates
+            .iter()
+            .filter(|c| c.to_lowercase().starts_with(&input))
+            .collect()
+    }
+    /// Complete the current [`input`] with the longest common prefix of the matching
+    /// `candidates`, see [`PopupCore::completions`]. If only a single candidate matches, the input
+    /// is completed to it in full. The cursor is moved to the end of the input.
+    pub fn complete(&mut self, candidates: &[String]) {
+        let matches = self.completions(candidates);
+        let first = match matches.first() {
+            Some(m) => m.chars().collect::<Vec<char>>(),
+            None => return,
+        };
+        // Find the number of characters all matches have in common.
+        let mut common = first.len();
+        for m in matches.iter().skip(1) {
+            common = m
+                .chars()
+                .zip(first.iter())
+                .take(common)
+                .take_while(|(a, b)| a == *b)
+                .count();
+        }
+        // Never shorten what the user has already typed.
+        if common >= self.input.chars().count() {
+            self.input = first[..common].iter().collect();
+            self.char_index = common;
+        }
+    }
     /// Set the [`entered_message`] field of the [`PopupCore`] structure to the current [`input`].
+    /// The [`submitted`] flag is raised so an empty message can be told apart from no message.
     pub fn submit_message(&mut self) {
         self.entered_message = self.input.clone();
+        self.submitted = true;
         self.input.clear();
         self.reset_cursor();
     }
 }
 
 /// Handle key events when the pop-up window is open, such as moving the cursor, typing and
-/// deleting text, entering the text or closing the pop-up.
+/// deleting text, entering the text or closing the pop-up. Clicking inside the pop-up confirms it,
+/// clicking outside of it cancels it.
 pub fn handle_key_events(app_state: &mut AppState) {
     match crossterm::event::poll(std::time::Duration::from_millis(50)) {
-        Ok(_) => {
+        // Without an event, return so the main loop can reload the library and redraw.
+        Ok(false) => return,
+        Ok(true) => {
             let event_read = match crossterm::event::read() {
                 Ok(event) => event,
                 Err(err) => {
@@ -109,6 +150,24 @@ pub fn handle_key_events(app_state: &mut AppState) {
                 }
             };
 
+            if let crossterm::event::Event::Mouse(mouse) = event_read {
+                if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+                    let inside = match app_state.rendered_areas.get().popup {
+                        Some(area) => RenderedAreas::contains(area, mouse.column, mouse.row),
+                        None => false,
+                    };
+                    if inside {
+                        // A confirmation pop-up is confirmed, any other pop-up is entered.
+                        if let PopupState::ConfirmDelete = app_state.popup_state {
+                            app_state.popup_core.input = String::from("y");
+                        }
+                        app_state.popup_core.submit_message();
+                    } else {
+                        app_state.set_default();
+                    }
+                }
+                return;
+            }
             if let crossterm::event::Event::Key(key) = event_read {
                 if key.kind == crossterm::event::KeyEventKind::Press {
                     match key.code {
@@ -117,6 +176,7 @@ pub fn handle_key_events(app_state: &mut AppState) {
                         KeyCode::Backspace => app_state.popup_core.delete_char(),
                         KeyCode::Left => app_state.popup_core.move_cursor_left(),
                         KeyCode::Right => app_state.popup_core.move_cursor_right(),
+                        KeyCode::Tab => app_state.popup_core.complete(&app_state.completions),
                         KeyCode::Esc => app_state.set_default(),
                         _ => {}
                     }
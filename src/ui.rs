@@ -1,6 +1,10 @@
 pub mod author;
+pub mod bibtex;
 pub mod description;
 pub mod explorer;
+pub mod help;
+pub mod layout;
+pub mod status;
 pub mod tags;
 pub mod title;
 pub mod ui_wrapper;
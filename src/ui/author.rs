@@ -8,22 +8,24 @@ pub fn render(file_load: &Loader, config: &Config, selected_idx: usize) -> Vec<L
     // Initialise rendered text
     let mut render_text: Vec<Line> = Vec::new();
 
-    // Get author and year from selected paper
-    let (author, year, journal) = match file_load.papers.get(selected_idx) {
+    // Get author, year, journal and reading status from selected paper
+    let (author, year, journal, status) = match file_load.papers.get(selected_idx) {
         Some(p) => {
             let mut auth_txt = String::new();
             for auth in &p.authors {
-                auth_txt.push_str(&auth.name);
+                auth_txt.push_str(&auth.display(config.layout.author_format));
                 auth_txt.push_str(" | ");
             }
             let year_txt = format!("Published year: {}", p.year);
             let journal_txt = format!("Published journal: {}", p.journal);
-            (auth_txt, year_txt, journal_txt)
+            let status_txt = format!("Reading status: {}", p.status);
+            (auth_txt, year_txt, journal_txt, status_txt)
         }
         None => (
             "Error retrieving authors".to_string(),
             "Error retrieving year".to_string(),
             "Error retrieving journal".to_string(),
+            "Error retrieving reading status".to_string(),
         ),
     };
 
@@ -39,6 +41,10 @@ pub fn render(file_load: &Loader, config: &Config, selected_idx: usize) -> Vec<L
         journal,
         Style::default().fg(config.colors.author_content),
     )));
+    render_text.push(Line::from(Span::styled(
+        status,
+        Style::default().fg(config.colors.author_content),
+    )));
 
     return render_text;
 }
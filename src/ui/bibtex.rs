@@ -0,0 +1,177 @@
+use crate::configuration::config::Config;
+use crate::file::loader::Loader;
+use ratatui::prelude::*;
+
+/// Parts of a bibtex entry that are highlighted differently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    EntryType,
+    Key,
+    FieldName,
+    Value,
+    Punctuation,
+}
+
+/// Where the scanner is inside a bibtex entry.
+enum ScanState {
+    Outside,
+    Key,
+    FieldName,
+    Value,
+}
+
+/// Render the bibtex block using the contents of the loaded paper pointed at by
+/// `selected_idx`. The entry type, citation key, field names and field values are
+/// highlighted with the bibtex colors of the configuration.
+pub fn render(file_load: &Loader, config: &Config, selected_idx: usize) -> Vec<Line<'static>> {
+    let bibtex = match file_load.papers.get(selected_idx) {
+        Some(p) => p.bibtex.clone(),
+        None => "Error retrieving info".to_string(),
+    };
+    // Split the highlighted parts into lines, keeping the style of each part.
+    let mut render_text: Vec<Line> = Vec::new();
+    let mut line: Vec<Span> = Vec::new();
+    for (token, text) in tokenize(&bibtex) {
+        let style = Style::default().fg(token_color(token, config));
+        let mut parts = text.split('\n').peekable();
+        while let Some(part) = parts.next() {
+            if !part.is_empty() {
+                line.push(Span::styled(part.to_string(), style));
+            }
+            if parts.peek().is_some() {
+                render_text.push(Line::from(std::mem::take(&mut line)));
+            }
+        }
+    }
+    render_text.push(Line::from(line));
+    return render_text;
+}
+
+/// Get the color used for the given `token`.
+fn token_color(token: Token, config: &Config) -> Color {
+    match token {
+        Token::EntryType => return config.colors.bibtex_entry_type,
+        Token::Key => return config.colors.bibtex_key,
+        Token::FieldName => return config.colors.bibtex_field,
+        Token::Value => return config.colors.bibtex_value,
+        Token::Punctuation => return config.colors.description_content,
+    }
+}
+
+/// Split a bibtex string into consecutive parts with the token they represent. Joining the
+/// parts gives back the original string. The scanner is lenient, so malformed bibtex is still
+/// shown, only highlighted less accurately.
+fn tokenize(bibtex: &str) -> Vec<(Token, String)> {
+    let chars: Vec<char> = bibtex.chars().collect();
+    let mut tokens: Vec<(Token, String)> = Vec::new();
+    let mut state = ScanState::Outside;
+    let mut i: usize = 0;
+    // Add the characters from `start` up to `end` as one token.
+    let mut push = |token: Token, start: usize, end: usize| {
+        if end > start {
+            tokens.push((token, chars[start..end].iter().collect()));
+        }
+    };
+    while i < chars.len() {
+        let start = i;
+        match state {
+            ScanState::Outside => {
+                if chars[i] == '@' {
+                    i += 1;
+                    while i < chars.len() && chars[i].is_alphanumeric() {
+                        i += 1;
+                    }
+                    push(Token::EntryType, start, i);
+                } else {
+                    if chars[i] == '{' || chars[i] == '(' {
+                        state = ScanState::Key;
+                    }
+                    i += 1;
+                    push(Token::Punctuation, start, i);
+                }
+            }
+            ScanState::Key => {
+                while i < chars.len() && !matches!(chars[i], ',' | '}' | ')') {
+                    i += 1;
+                }
+                push(Token::Key, start, i);
+                state = ScanState::FieldName;
+            }
+            ScanState::FieldName => {
+                if chars[i].is_alphanumeric() || matches!(chars[i], '_' | '-' | ':' | '.') {
+                    while i < chars.len()
+                        && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '-' | ':' | '.'))
+                    {
+                        i += 1;
+                    }
+                    push(Token::FieldName, start, i);
+                } else {
+                    match chars[i] {
+                        '=' => state = ScanState::Value,
+                        '}' | ')' => state = ScanState::Outside,
+                        _ => {}
+                    }
+                    i += 1;
+                    push(Token::Punctuation, start, i);
+                }
+            }
+            ScanState::Value => match chars[i] {
+                '{' => {
+                    // Braced value, which may contain nested braces.
+                    let mut depth: usize = 0;
+                    while i < chars.len() {
+                        match chars[i] {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            _ => {}
+                        }
+                        i += 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    push(Token::Value, start, i);
+                }
+                '"' => {
+                    // Quoted value, quotes inside braces do not end it.
+                    let mut depth: usize = 0;
+                    i += 1;
+                    while i < chars.len() && !(chars[i] == '"' && depth == 0) {
+                        match chars[i] {
+                            '{' => depth += 1,
+                            '}' => depth = depth.saturating_sub(1),
+                            _ => {}
+                        }
+                        i += 1;
+                    }
+                    i = (i + 1).min(chars.len());
+                    push(Token::Value, start, i);
+                }
+                ',' | '}' | ')' => {
+                    state = if chars[i] == ',' {
+                        ScanState::FieldName
+                    } else {
+                        ScanState::Outside
+                    };
+                    i += 1;
+                    push(Token::Punctuation, start, i);
+                }
+                c if c.is_whitespace() || c == '#' => {
+                    i += 1;
+                    push(Token::Punctuation, start, i);
+                }
+                _ => {
+                    // Bare value, e.g. a number or a string macro.
+                    while i < chars.len()
+                        && !chars[i].is_whitespace()
+                        && !matches!(chars[i], ',' | '}' | ')' | '#')
+                    {
+                        i += 1;
+                    }
+                    push(Token::Value, start, i);
+                }
+            },
+        }
+    }
+    return tokens;
+}
@@ -5,19 +5,33 @@ use ratatui::prelude::*;
 /// Render the explorer side of the TUI. The title of all the loaded
 /// papers is displayed. The title of the paper pointed at by
 /// `selected_idx` is prepended by a selection icon as determined
-/// by the configuration file.
+/// by the configuration file. Marked papers, including the papers in
+/// the visual range started at `visual_anchor`, are prepended by the
+/// mark icon instead of the file icon.
 pub fn render(
     file_load: &Loader,
     config: &Config,
     selected_idx: usize,
     explorer_area: Rect,
+    visual_anchor: Option<usize>,
 ) -> Vec<Line<'static>> {
     // Initialise the text
     let mut render_text: Vec<Line> = Vec::new();
+    // Get the visual range, in terms of indices of `Loader.valid_paths`.
+    let visual_range = match (visual_anchor, file_load.path_index(selected_idx)) {
+        (Some(anchor), Some(current)) => Some((anchor.min(current), anchor.max(current))),
+        _ => None,
+    };
 
     // Enumerate through the papers
     for (i, paper) in file_load.papers.iter().enumerate() {
         let mut line = String::new();
+        let path_idx = file_load.path_index(i).unwrap_or(usize::MAX);
+        let in_visual_range = match visual_range {
+            Some((low, high)) => low <= path_idx && path_idx <= high,
+            None => false,
+        };
+        let marked = file_load.is_marked(path_idx) || in_visual_range;
         let mut style = Style::default()
             .fg(config.colors.explorer_unselected_fg)
             .bg(config.colors.explorer_unselected_bg);
@@ -29,10 +43,16 @@ pub fn render(
                 .fg(config.colors.explorer_selected_fg)
                 .bg(config.colors.explorer_selected_bg)
                 .add_modifier(Modifier::BOLD);
+        } else if marked {
+            let mark_icon = config.general.mark_icon.clone();
+            line.push_str(&mark_icon);
         } else {
             let file_icon = config.general.file_icon.clone();
             line.push_str(&file_icon);
         }
+        if marked {
+            style = style.fg(config.colors.explorer_marked_fg);
+        }
         line.push_str(&paper.title);
         let line = truncate_string(line, explorer_area);
         render_text.push(Line::from(Span::styled(line, style)));
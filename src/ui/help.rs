@@ -0,0 +1,96 @@
+use crate::configuration::config::Config;
+use crate::key::event::{bound_actions, KeyEvents};
+use ratatui::prelude::*;
+
+/// Keys that are used inside the text input pop-up windows. These are fixed
+/// and cannot be configured.
+const POPUP_KEYS: [(&str, &str); 7] = [
+    ("<Enter>", "Submit the input"),
+    ("<Esc>", "Cancel and close the pop-up"),
+    ("<Tab>", "Complete the input, where completions are offered"),
+    ("<Left>, <Right>", "Move the cursor"),
+    ("<BS>", "Delete the character before the cursor"),
+    ("Click inside", "Submit the input"),
+    ("Click outside", "Cancel and close the pop-up"),
+];
+
+/// Render the help overlay, listing every action together with the keys that
+/// are currently bound to it, grouped by the mode in which the keys are used.
+pub fn render(config: &Config) -> Vec<Line<'static>> {
+    // Gather the (keys, description) pairs of each mode.
+    // Collapsing and expanding tags is only done inside the tag browser.
+    let mut normal: Vec<(String, &str)> = bound_actions(config)
+        .iter()
+        .filter(|(action, _, _)| !matches!(action, KeyEvents::CollapseTag | KeyEvents::ExpandTag))
+        .map(|(action, _, bindings)| (bindings.to_string(), action.description()))
+        .collect();
+    normal.push((String::from("Click"), KeyEvents::Click(0, 0).description()));
+    normal.push((
+        String::from("Scroll"),
+        KeyEvents::ScrollUp(0, 0).description(),
+    ));
+    let popup: Vec<(String, &str)> = POPUP_KEYS
+        .iter()
+        .map(|(keys, description)| (keys.to_string(), *description))
+        .collect();
+    let k = &config.keybinds;
+    let tag_browser: Vec<(String, &str)> = vec![
+        (
+            format!("{}, {}", k.next, k.previous),
+            "Move through the tags",
+        ),
+        (
+            k.collapse_tag.to_string(),
+            KeyEvents::CollapseTag.description(),
+        ),
+        (k.expand_tag.to_string(), KeyEvents::ExpandTag.description()),
+        (
+            k.toggle_mark.to_string(),
+            "Select or deselect the tag, selecting a tag includes its children",
+        ),
+        (k.clear_marks.to_string(), "Deselect all tags"),
+        (
+            format!("{}, <Esc>", k.tag_browser),
+            "Return to the papers, keeping the filter",
+        ),
+        (String::from("Other keys"), "Act on the papers as usual"),
+    ];
+    let sections = [
+        ("Normal mode", normal),
+        ("Tag browser mode", tag_browser),
+        ("Pop-up mode", popup),
+    ];
+
+    // Align the descriptions by padding all keys to the same width.
+    let key_width = sections
+        .iter()
+        .flat_map(|(_, entries)| entries.iter().map(|(keys, _)| keys.chars().count()))
+        .max()
+        .unwrap_or(0);
+
+    let mut render_text: Vec<Line> = Vec::new();
+    for (mode, entries) in sections {
+        if !render_text.is_empty() {
+            render_text.push(Line::from(""));
+        }
+        render_text.push(Line::from(Span::styled(
+            mode.to_string(),
+            Style::default()
+                .fg(config.colors.content_block_title)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for (keys, description) in entries {
+            render_text.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:<key_width$}  ", keys),
+                    Style::default().fg(config.colors.popup_text),
+                ),
+                Span::styled(
+                    description.to_string(),
+                    Style::default().fg(config.colors.description_content),
+                ),
+            ]));
+        }
+    }
+    return render_text;
+}
@@ -0,0 +1,145 @@
+use crate::configuration::config::{Config, ContentPane};
+use ratatui::prelude::*;
+
+/// Areas of the terminal the parts of the TUI are drawn in, following the
+/// `[layout]` section of the configuration file.
+///
+/// The `explorer_master` and `content_master` fields contain the areas of the
+/// two master blocks, the `explorer` and `tags` fields the blocks inside the
+/// explorer master block, and the `content` field the shown content panes in
+/// the order they are drawn. The `tags` field is `None` if the tags are hidden.
+/// The `status` field contains the line of the status bar.
+#[derive(Clone, Debug)]
+pub struct PaneAreas {
+    pub explorer_master: Rect,
+    pub explorer: Rect,
+    pub tags: Option<Rect>,
+    pub content_master: Rect,
+    pub content: Vec<(ContentPane, Rect)>,
+    pub status: Rect,
+}
+
+/// Check if the TUI drawn in `area` uses the stacked layout, with the explorer
+/// above the content instead of next to it.
+pub fn is_stacked(area: Rect, config: &Config) -> bool {
+    let threshold = config.layout.stack_below_width;
+    return threshold > 0 && area.width < threshold;
+}
+
+/// Divide the terminal `area` into the areas of the parts of the TUI. The
+/// bibtex pane is only shown if `show_bibtex` is set, below the other content
+/// panes if it is not part of the configured `content_panes`.
+pub fn compute_areas(area: Rect, config: &Config, show_bibtex: bool) -> PaneAreas {
+    let layout = &config.layout;
+    // Keep the bottom line of the terminal for the status bar.
+    let screen_layout = Layout::new(
+        Direction::Vertical,
+        [Constraint::Min(0), Constraint::Length(1)],
+    )
+    .split(area);
+    let master_layout = if is_stacked(area, config) {
+        let explorer_height = layout.stacked_explorer_height.min(100);
+        Layout::new(
+            Direction::Vertical,
+            [
+                Constraint::Percentage(explorer_height),
+                Constraint::Percentage(100 - explorer_height),
+            ],
+        )
+        .split(screen_layout[0])
+    } else {
+        let explorer_width = layout.explorer_width.min(100);
+        Layout::new(
+            Direction::Horizontal,
+            [
+                Constraint::Percentage(explorer_width),
+                Constraint::Percentage(100 - explorer_width),
+            ],
+        )
+        .split(screen_layout[0])
+    };
+
+    // Paper explorer, with the tags below it if they are shown.
+    let (explorer, tags) = if layout.show_tags {
+        let tags_height = layout.tags_height.min(100);
+        let explorer_layout = Layout::new(
+            Direction::Vertical,
+            [
+                Constraint::Percentage(100 - tags_height),
+                Constraint::Percentage(tags_height),
+            ],
+        )
+        .margin(2)
+        .split(master_layout[0]);
+        (explorer_layout[0], Some(explorer_layout[1]))
+    } else {
+        let explorer_layout = Layout::new(Direction::Vertical, [Constraint::Min(0)])
+            .margin(2)
+            .split(master_layout[0]);
+        (explorer_layout[0], None)
+    };
+
+    // Content panes, sharing the height in proportion to their configured heights.
+    let mut panes: Vec<ContentPane> = layout
+        .content_panes
+        .iter()
+        .cloned()
+        .filter(|pane| *pane != ContentPane::Bibtex || show_bibtex)
+        .collect();
+    if show_bibtex && !panes.contains(&ContentPane::Bibtex) {
+        panes.push(ContentPane::Bibtex);
+    }
+    let constraints: Vec<Constraint> = panes
+        .iter()
+        .map(|pane| {
+            let height = match pane {
+                ContentPane::Title => layout.title_height,
+                ContentPane::Authors => layout.authors_height,
+                ContentPane::Description => layout.description_height,
+                ContentPane::Bibtex => layout.bibtex_height,
+            };
+            Constraint::Fill(height.max(1))
+        })
+        .collect();
+    let content_layout = Layout::new(Direction::Vertical, constraints)
+        .margin(2)
+        .split(master_layout[1]);
+    let content = panes
+        .into_iter()
+        .zip(content_layout.iter().cloned())
+        .collect();
+
+    return PaneAreas {
+        explorer_master: master_layout[0],
+        explorer,
+        tags,
+        content_master: master_layout[1],
+        content,
+        status: screen_layout[1],
+    };
+}
+
+/// Compute the `load` parameter of the `Loader` such that the loaded papers exactly fill the
+/// explorer, as laid out by the `[layout]` section of the configuration for the current terminal
+/// size.
+pub fn compute_loader_size(config: &Config) -> i32 {
+    match termsize::get() {
+        Some(size) => {
+            return loader_size_for_area(Rect::new(0, 0, size.cols, size.rows), config);
+        }
+        None => {
+            log::warn!("Could not obtain terminal size. Default value will be used.");
+            return 20;
+        }
+    }
+}
+
+/// Compute the `load` parameter of the `Loader` such that the loaded papers exactly fill the
+/// explorer when the TUI is drawn inside `area`.
+pub fn loader_size_for_area(area: Rect, config: &Config) -> i32 {
+    let areas = compute_areas(area, config, false);
+    // -2 because of the borders of the explorer block, and -1 because the window holds
+    // one paper more than the `load` parameter.
+    let tui_rows = areas.explorer.height as i32 - 2 - 1;
+    return tui_rows.max(0);
+}
@@ -0,0 +1,81 @@
+use crate::configuration::config::Config;
+use crate::file::loader::ActionResult;
+use ratatui::prelude::*;
+
+/// Message shown in the status bar at the bottom of the TUI.
+///
+/// The `text` field contains the message itself.
+/// The `is_error` field tells if the message reports a failure, which changes its color.
+/// The `shown_at` field contains the moment the message was first shown, so it can be removed
+/// once `Config.general.status_timeout` has passed.
+#[derive(Clone, Debug)]
+pub struct StatusMessage {
+    pub text: String,
+    pub is_error: bool,
+    pub shown_at: std::time::Instant,
+}
+
+impl StatusMessage {
+    /// Create a `StatusMessage` from the outcome of an action.
+    pub fn from_result(result: ActionResult) -> Self {
+        let (text, is_error) = match result {
+            Ok(text) => (text, false),
+            Err(text) => (text, true),
+        };
+        StatusMessage {
+            text,
+            is_error,
+            shown_at: std::time::Instant::now(),
+        }
+    }
+    /// Check if the message has been shown for longer than `timeout` milliseconds. A `timeout`
+    /// of zero keeps the message until it is replaced.
+    pub fn is_expired(&self, timeout: u64) -> bool {
+        return timeout > 0 && self.shown_at.elapsed().as_millis() >= timeout as u128;
+    }
+}
+
+/// Send the `message` as a desktop notification with `notify-send`. The command runs in the
+/// background, so a slow or missing notification daemon never blocks the TUI.
+pub fn send_notification(message: &str) {
+    match std::process::Command::new("notify-send")
+        .arg(message)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+    {
+        Ok(mut child) => {
+            // Wait for the command on another thread, so it does not linger as a zombie.
+            std::thread::spawn(move || child.wait());
+        }
+        Err(err) => {
+            log::warn!("Error sending notification with `notify-send`: {err}");
+        }
+    }
+}
+
+/// Render the status bar, showing the current `status` message if there is one, and a hint
+/// on how to open the help otherwise.
+pub fn render(status: &Option<StatusMessage>, config: &Config) -> Line<'static> {
+    match status {
+        Some(message) => {
+            let color = if message.is_error {
+                config.colors.status_error_fg
+            } else {
+                config.colors.status_success_fg
+            };
+            return Line::from(Span::styled(
+                format!(" {}", message.text),
+                Style::default().fg(color),
+            ));
+        }
+        None => {
+            return Line::from(Span::styled(
+                format!(" Press {} for help", config.keybinds.help.primary()),
+                Style::default()
+                    .fg(config.colors.content_block_border)
+                    .add_modifier(Modifier::DIM),
+            ));
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use crate::configuration::config::Config;
 
 use crate::file::loader::Loader;
+use crate::file::normalize::TagNormalizer;
+use crate::listing::tags::{get_tag_tree, TagTreeEntry};
 use ratatui::prelude::*;
+use std::collections::HashSet;
 
 /// Render the tags block using the contents of the loaded paper
 /// pointed at by `selected_idx`.
@@ -28,3 +31,179 @@ pub fn render(file_load: &Loader, config: &Config, selected_idx: usize) -> Vec<L
     )));
     return render_text;
 }
+
+/// State of the tag browser, which replaces the tags block with a tree of all
+/// the tags in the library that can be selected to filter the explorer.
+///
+/// The `active` field tells if the tag browser is shown and has the focus.
+/// The `tags` field contains the whole tag tree, with the amount of papers for every tag.
+/// The `collapsed` field contains the paths of the tags whose children are hidden.
+/// The `cursor` field contains the index in the visible tags of the highlighted tag.
+#[derive(Clone, Debug, Default)]
+pub struct TagBrowser {
+    pub active: bool,
+    pub tags: Vec<TagTreeEntry>,
+    pub collapsed: HashSet<String>,
+    pub cursor: usize,
+}
+
+impl TagBrowser {
+    /// Show the tag browser with the tags of the papers in `folderdir`, compared with the
+    /// `normalizer`. Tags in the `tag_filters` that no paper has are listed as well, so they
+    /// can be deselected.
+    pub fn open(
+        &mut self,
+        folderdir: &std::path::PathBuf,
+        tag_filters: &[String],
+        normalizer: &TagNormalizer,
+    ) {
+        self.tags = get_tag_tree(folderdir, tag_filters, normalizer);
+        let tags = &self.tags;
+        self.collapsed
+            .retain(|path| tags.iter().any(|t| t.path == *path && t.has_children));
+        self.cursor = self.cursor.min(self.visible().len().saturating_sub(1));
+        self.active = true;
+    }
+    /// Get the tags that are shown, i.e. those without a collapsed parent.
+    pub fn visible(&self) -> Vec<&TagTreeEntry> {
+        let mut visible: Vec<&TagTreeEntry> = Vec::new();
+        // Depth of the collapsed tag whose children are being skipped, if any.
+        let mut skip_below: Option<usize> = None;
+        for entry in self.tags.iter() {
+            if let Some(depth) = skip_below {
+                if entry.depth > depth {
+                    continue;
+                }
+                skip_below = None;
+            }
+            if self.collapsed.contains(&entry.path) {
+                skip_below = Some(entry.depth);
+            }
+            visible.push(entry);
+        }
+        return visible;
+    }
+    /// Move the cursor by `delta` tags, staying inside the list.
+    pub fn move_cursor(&mut self, delta: isize) {
+        let last = self.visible().len().saturating_sub(1) as isize;
+        self.cursor = (self.cursor as isize + delta).clamp(0, last) as usize;
+    }
+    /// Hide the children of the tag under the cursor. If they are hidden already, or
+    /// the tag has none, the cursor moves to its parent instead.
+    pub fn collapse(&mut self) {
+        let visible = self.visible();
+        let entry = match visible.get(self.cursor) {
+            Some(e) => *e,
+            None => return,
+        };
+        if entry.has_children && !self.collapsed.contains(&entry.path) {
+            let path = entry.path.clone();
+            self.collapsed.insert(path);
+        } else if entry.depth > 0 {
+            let parent = visible[..self.cursor]
+                .iter()
+                .rposition(|t| t.depth < entry.depth);
+            if let Some(i) = parent {
+                self.cursor = i;
+            }
+        }
+    }
+    /// Show the children of the tag under the cursor.
+    pub fn expand(&mut self) {
+        let path = match self.visible().get(self.cursor) {
+            Some(e) => e.path.clone(),
+            None => return,
+        };
+        self.collapsed.remove(&path);
+    }
+    /// Index of the first tag shown when the browser is drawn in `area`, chosen so
+    /// the cursor stays visible.
+    pub fn offset(&self, area: Rect) -> usize {
+        // Lines available inside the borders.
+        let visible = area.height.saturating_sub(2).max(1) as usize;
+        return (self.cursor + 1).saturating_sub(visible);
+    }
+    /// Get the `tag_filters` with the tag under the cursor added if it was not in
+    /// there, and removed otherwise. Tags are compared with the `normalizer`.
+    pub fn toggled_filters(
+        &self,
+        tag_filters: &[String],
+        normalizer: &TagNormalizer,
+    ) -> Vec<String> {
+        let mut filters = tag_filters.to_vec();
+        if let Some(entry) = self.visible().get(self.cursor) {
+            match filters.iter().position(|t| normalizer.same(t, &entry.path)) {
+                Some(i) => {
+                    filters.remove(i);
+                }
+                None => filters.push(entry.path.clone()),
+            }
+        }
+        return filters;
+    }
+}
+
+/// Render the tag browser, showing the visible part of the tag tree with the amount of
+/// papers for every tag. Tags with children are marked as expanded or collapsed, the tags
+/// in the `Loader.tag_filters` are checked, and the tag under the cursor is highlighted.
+/// The list scrolls so the cursor stays visible inside `area`.
+pub fn render_browser(
+    browser: &TagBrowser,
+    file_load: &Loader,
+    config: &Config,
+    area: Rect,
+) -> Vec<Line<'static>> {
+    let visible = area.height.saturating_sub(2).max(1) as usize;
+    let offset = browser.offset(area);
+    let mut render_text: Vec<Line> = Vec::new();
+    for (i, entry) in browser
+        .visible()
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(visible)
+    {
+        let check = if file_load
+            .tag_filters
+            .iter()
+            .any(|t| file_load.tag_normalizer.same(t, &entry.path))
+        {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+        let fold = if !entry.has_children {
+            " "
+        } else if browser.collapsed.contains(&entry.path) {
+            "▸"
+        } else {
+            "▾"
+        };
+        let style = if i == browser.cursor {
+            Style::default()
+                .fg(config.colors.explorer_selected_fg)
+                .bg(config.colors.explorer_selected_bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(config.colors.tag_content)
+        };
+        render_text.push(Line::from(Span::styled(
+            format!(
+                "{}{} {} {} ({})",
+                "  ".repeat(entry.depth),
+                fold,
+                check,
+                entry.name(),
+                entry.count
+            ),
+            style,
+        )));
+    }
+    if browser.tags.is_empty() {
+        render_text.push(Line::from(Span::styled(
+            "No tags in the library",
+            Style::default().fg(config.colors.tag_content),
+        )));
+    }
+    return render_text;
+}
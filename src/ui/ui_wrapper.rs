@@ -1,11 +1,17 @@
 use super::author;
+use super::bibtex;
 use super::description;
 use super::explorer;
+use super::help;
+use super::layout;
+use super::status;
 use super::tags;
 use super::title;
-use super::window::{AppState, PopupState};
-use crate::configuration::config::Config;
+use super::window::{AppState, PopupState, RenderedAreas};
+use crate::configuration::config::{Config, ContentPane};
 use crate::file::loader::Loader;
+use crate::file::parser::PaperField;
+use crate::popup::popup::PopupCore;
 use ratatui::{prelude::*, widgets::*};
 
 /// Given all the needed program information e.g. the loaded papers,
@@ -19,23 +25,15 @@ pub fn ui_pre_args<'a>(
     selected_idx: usize,
 ) -> Box<dyn Fn(&mut Frame) + 'a> {
     Box::new(move |frame: &mut Frame| {
-        let master_layout = Layout::new(
-            Direction::Horizontal,
-            [Constraint::Percentage(30), Constraint::Percentage(70)],
-        )
-        .split(frame.size());
-        let explorer_rect = master_layout[0].clone();
-
-        // Paper explorer UI
-        let explorer_layout = Layout::new(
-            Direction::Vertical,
-            [Constraint::Percentage(85), Constraint::Percentage(15)],
-        )
-        .margin(2)
-        .split(master_layout[0]);
+        let areas = layout::compute_areas(frame.size(), config, app_state.show_bibtex);
 
+        // Show the name of the library, if it has one.
+        let explorer_master_title = match &file_load.library_name {
+            Some(name) => format!(" Paper Explorer ({}) ", name),
+            None => String::from(" Paper Explorer "),
+        };
         let explorer_master_block = Block::new()
-            .title(" Paper Explorer ")
+            .title(explorer_master_title)
             .title_alignment(Alignment::Center)
             .title_style(
                 Style::default()
@@ -46,8 +44,19 @@ pub fn ui_pre_args<'a>(
             .borders(Borders::ALL)
             .border_style(Style::default().fg(config.colors.master_block_border));
 
+        // Show the position of the selected paper, and how many papers are marked, if any.
+        let position = match file_load.path_index(selected_idx) {
+            Some(i) => format!("{}/{}", i + 1, file_load.valid_paths.len()),
+            None => format!("0/{}", file_load.valid_paths.len()),
+        };
+        let marked_count = file_load.marked.len();
+        let explorer_title = if marked_count > 0 {
+            format!(" Titles {} ({} marked) ", position, marked_count)
+        } else {
+            format!(" Titles {} ", position)
+        };
         let explorer_block = Block::new()
-            .title(" Titles ")
+            .title(explorer_title)
             .title_alignment(Alignment::Center)
             .title_style(
                 Style::default()
@@ -57,14 +66,39 @@ pub fn ui_pre_args<'a>(
             .border_type(BorderType::Plain)
             .borders(Borders::ALL)
             .border_style(Style::default().fg(config.colors.content_block_border));
-        let explorer_area = explorer_layout[0].clone();
-        let explorer_render = explorer::render(file_load, config, selected_idx, explorer_area);
+        let explorer_area = areas.explorer;
+        let explorer_render = explorer::render(
+            file_load,
+            config,
+            selected_idx,
+            explorer_area,
+            app_state.visual_anchor,
+        );
         let explorer_paragraph = Paragraph::new(explorer_render)
             .block(explorer_block)
             .alignment(Alignment::Left);
 
+        // The tags block shows the tag browser when it has the focus, and the tag filter
+        // in its title otherwise.
+        let tag_browser = &app_state.tag_browser;
+        let tag_title = if tag_browser.active {
+            format!(
+                " Tags ({} to filter, {} to close) ",
+                config.keybinds.toggle_mark.primary(),
+                config.keybinds.tag_browser.primary()
+            )
+        } else if !file_load.tag_filters.is_empty() {
+            format!(" Tags (filter: {}) ", file_load.tag_filters.join(", "))
+        } else {
+            String::from(" Tags ")
+        };
+        let tag_border_color = if tag_browser.active {
+            config.colors.popup_block_border
+        } else {
+            config.colors.content_block_border
+        };
         let tag_block = Block::new()
-            .title(" Tags ")
+            .title(tag_title)
             .title_alignment(Alignment::Center)
             .title_style(
                 Style::default()
@@ -73,24 +107,23 @@ pub fn ui_pre_args<'a>(
             )
             .border_type(BorderType::Plain)
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(config.colors.content_block_border));
-        let tag_render = tags::render(file_load, config, selected_idx);
-        let tag_paragraph = Paragraph::new(tag_render)
+            .border_style(Style::default().fg(tag_border_color));
+        let tag_paragraph = if tag_browser.active {
+            Paragraph::new(tags::render_browser(
+                tag_browser,
+                file_load,
+                config,
+                areas.tags.unwrap_or_default(),
+            ))
             .block(tag_block)
             .alignment(Alignment::Left)
-            .wrap(Wrap { trim: true });
+        } else {
+            Paragraph::new(tags::render(file_load, config, selected_idx))
+                .block(tag_block)
+                .alignment(Alignment::Left)
+                .wrap(Wrap { trim: true })
+        };
         // Paper content viewer UI
-        let content_layout = Layout::new(
-            Direction::Vertical,
-            [
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
-                Constraint::Percentage(60),
-            ],
-        )
-        .margin(2)
-        .split(master_layout[1]);
-
         let content_block = Block::new()
             .title(" Content ")
             .title_alignment(Alignment::Center)
@@ -152,25 +185,66 @@ pub fn ui_pre_args<'a>(
         let desc_paragraph = Paragraph::new(desc_render)
             .block(desc_block)
             .alignment(Alignment::Left)
-            .wrap(Wrap { trim: true });
+            .wrap(Wrap { trim: true })
+            .scroll((app_state.description_scroll, 0));
 
-        frame.render_widget(explorer_master_block, master_layout[0]);
-        frame.render_widget(explorer_paragraph, explorer_layout[0]);
-        frame.render_widget(tag_paragraph, explorer_layout[1]);
-        frame.render_widget(content_block, master_layout[1]);
-        frame.render_widget(title_paragraph, content_layout[0]);
-        frame.render_widget(author_paragraph, content_layout[1]);
-        frame.render_widget(desc_paragraph, content_layout[2]);
+        let bibtex_block = Block::new()
+            .title(" Bibtex ")
+            .title_alignment(Alignment::Center)
+            .title_style(
+                Style::default()
+                    .fg(config.colors.content_block_title)
+                    .add_modifier(Modifier::ITALIC),
+            )
+            .border_type(BorderType::Plain)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(config.colors.content_block_border));
+        let bibtex_render = bibtex::render(&file_load, &config, selected_idx);
+        let bibtex_paragraph = Paragraph::new(bibtex_render)
+            .block(bibtex_block)
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false });
 
+        frame.render_widget(explorer_master_block, areas.explorer_master);
+        frame.render_widget(explorer_paragraph, areas.explorer);
+        if let Some(tags_area) = areas.tags {
+            frame.render_widget(tag_paragraph, tags_area);
+        }
+        frame.render_widget(content_block, areas.content_master);
+        // Draw the content panes in the configured order.
+        let mut description_area = Rect::default();
+        for (pane, pane_area) in areas.content.iter() {
+            match pane {
+                ContentPane::Title => frame.render_widget(title_paragraph.clone(), *pane_area),
+                ContentPane::Authors => frame.render_widget(author_paragraph.clone(), *pane_area),
+                ContentPane::Description => {
+                    frame.render_widget(desc_paragraph.clone(), *pane_area);
+                    description_area = *pane_area;
+                }
+                ContentPane::Bibtex => frame.render_widget(bibtex_paragraph.clone(), *pane_area),
+            }
+        }
+        frame.render_widget(
+            Paragraph::new(status::render(&app_state.status, config)),
+            areas.status,
+        );
+
+        let mut confirm_popup_area: Option<Rect> = None;
         if let PopupState::ConfirmDelete = app_state.popup_state {
+            let target_count = file_load.target_indices(selected_idx).len();
+            let delete_title = if target_count > 1 {
+                format!(" Confirm delete of {} papers (y/n) ", target_count)
+            } else {
+                String::from(" Confirm delete (y/n) ")
+            };
             let block = Block::new()
-                .title(" Confirm delete (y/n) ")
+                .title(delete_title)
                 .title_alignment(Alignment::Left)
                 .title_style(Style::default().fg(config.colors.popup_block_title))
                 .border_type(BorderType::Rounded)
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(config.colors.popup_block_border));
-            let popup_area = get_popup_rect(selected_idx, explorer_rect);
+            let popup_area = get_popup_rect(selected_idx, areas.explorer_master, frame.size());
             let popup_text = pad_string_back(
                 app_state.popup_core.input.clone(),
                 popup_area.width as usize,
@@ -183,23 +257,296 @@ pub fn ui_pre_args<'a>(
                 .block(block)
                 .alignment(Alignment::Left)
                 .wrap(Wrap { trim: true });
-            frame.render_widget(popup_par, popup_area)
+            frame.render_widget(popup_par, popup_area);
+            confirm_popup_area = Some(popup_area);
         }
+        let input_popup_area = match app_state.popup_state {
+            PopupState::SelectField => {
+                let names: Vec<&str> = PaperField::ALL.iter().map(|f| f.name()).collect();
+                let title = format!(" Field to edit ({}) ", names.join("/"));
+                Some(render_input_popup(
+                    frame,
+                    config,
+                    &title,
+                    &app_state.popup_core,
+                    areas.content_master,
+                    Vec::new(),
+                ))
+            }
+            PopupState::EditField(field) => {
+                let title = format!(" Edit {} (Enter to save, Esc to cancel) ", field.name());
+                Some(render_input_popup(
+                    frame,
+                    config,
+                    &title,
+                    &app_state.popup_core,
+                    areas.content_master,
+                    Vec::new(),
+                ))
+            }
+            PopupState::EditTags => {
+                let title = " Toggle tag (Tab to complete, Enter to add/remove, Esc to close) ";
+                let targets = file_load.target_indices(selected_idx);
+                let current = if targets.len() > 1 {
+                    format!("{} marked papers", targets.len())
+                } else {
+                    match file_load.papers.get(selected_idx) {
+                        Some(p) => p
+                            .tags
+                            .iter()
+                            .map(|t| t.label.clone())
+                            .collect::<Vec<String>>()
+                            .join(" | "),
+                        None => String::new(),
+                    }
+                };
+                let matches = app_state
+                    .popup_core
+                    .completions(&app_state.completions)
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" | ");
+                let footer = vec![
+                    Line::from(Span::styled(
+                        format!("Current: {}", current),
+                        Style::default().fg(config.colors.tag_content),
+                    )),
+                    Line::from(Span::styled(
+                        format!("Matches: {}", matches),
+                        Style::default().fg(config.colors.popup_text),
+                    )),
+                ];
+                Some(render_input_popup(
+                    frame,
+                    config,
+                    title,
+                    &app_state.popup_core,
+                    areas.content_master,
+                    footer,
+                ))
+            }
+            PopupState::SetStatus => {
+                let target_count = file_load.target_indices(selected_idx).len();
+                let title = format!(
+                    " Reading status of {} paper(s) (Tab to complete, Esc to cancel) ",
+                    target_count
+                );
+                let matches = app_state
+                    .popup_core
+                    .completions(&app_state.completions)
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" | ");
+                let footer = vec![Line::from(Span::styled(
+                    format!("Matches: {}", matches),
+                    Style::default().fg(config.colors.popup_text),
+                ))];
+                Some(render_input_popup(
+                    frame,
+                    config,
+                    &title,
+                    &app_state.popup_core,
+                    areas.content_master,
+                    footer,
+                ))
+            }
+            PopupState::GoToIndex => {
+                let title = format!(
+                    " Go to paper (1-{}, Esc to cancel) ",
+                    file_load.valid_paths.len()
+                );
+                Some(render_input_popup(
+                    frame,
+                    config,
+                    &title,
+                    &app_state.popup_core,
+                    areas.content_master,
+                    Vec::new(),
+                ))
+            }
+            PopupState::SwitchLibrary => {
+                let title = " Switch to library (Tab to complete, Esc to cancel) ";
+                let current = match &file_load.library_name {
+                    Some(name) => name.clone(),
+                    None => file_load.folderdir.to_string_lossy().to_string(),
+                };
+                let matches = app_state
+                    .popup_core
+                    .completions(&app_state.completions)
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" | ");
+                let footer = vec![
+                    Line::from(Span::styled(
+                        format!("Current: {}", current),
+                        Style::default().fg(config.colors.popup_text),
+                    )),
+                    Line::from(Span::styled(
+                        format!("Matches: {}", matches),
+                        Style::default().fg(config.colors.popup_text),
+                    )),
+                ];
+                Some(render_input_popup(
+                    frame,
+                    config,
+                    title,
+                    &app_state.popup_core,
+                    areas.content_master,
+                    footer,
+                ))
+            }
+            PopupState::ExportBibtex => {
+                let target_count = file_load.target_indices(selected_idx).len();
+                let title = format!(
+                    " Export bibtex of {} paper(s) to file (Esc to cancel) ",
+                    target_count
+                );
+                Some(render_input_popup(
+                    frame,
+                    config,
+                    &title,
+                    &app_state.popup_core,
+                    areas.content_master,
+                    Vec::new(),
+                ))
+            }
+            _ => None,
+        };
+        let help_area = match app_state.popup_state {
+            PopupState::Help => {
+                let area = get_help_rect(frame.size());
+                let title = format!(
+                    " Help ({} or Esc to close, {}/{} to scroll) ",
+                    config.keybinds.help.primary(),
+                    config.keybinds.next.primary(),
+                    config.keybinds.previous.primary()
+                );
+                let block = Block::new()
+                    .title(title)
+                    .title_alignment(Alignment::Center)
+                    .title_style(Style::default().fg(config.colors.popup_block_title))
+                    .border_type(BorderType::Rounded)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(config.colors.popup_block_border));
+                let help_par = Paragraph::new(help::render(config))
+                    .block(block)
+                    .alignment(Alignment::Left)
+                    .scroll((app_state.help_scroll, 0));
+                frame.render_widget(Clear, area);
+                frame.render_widget(help_par, area);
+                Some(area)
+            }
+            _ => None,
+        };
+        // Remember where everything was drawn, so mouse clicks can be mapped onto it.
+        app_state.rendered_areas.set(RenderedAreas {
+            explorer: areas.explorer,
+            description: description_area,
+            tags: areas.tags.unwrap_or_default(),
+            popup: confirm_popup_area.or(input_popup_area).or(help_area),
+        });
     })
 }
 
-/// Define the location of the pop-up window.
-pub fn get_popup_rect(selected_idx: usize, explorer_rect: Rect) -> Rect {
+/// Render a pop-up window with a text input on top of the `content_rect`. Unlike the confirmation
+/// pop-up, the input is wrapped over as many lines as needed so long fields such as the
+/// description or bibtex can be edited in full. The terminal cursor is placed at the cursor of the
+/// `PopupCore`. Any `footer` lines are shown below the input, e.g. to list completions. The area
+/// the pop-up was drawn in is returned.
+pub fn render_input_popup(
+    frame: &mut Frame,
+    config: &Config,
+    title: &str,
+    popup_core: &PopupCore,
+    content_rect: Rect,
+    footer: Vec<Line<'static>>,
+) -> Rect {
+    // Width available for text inside the borders.
+    let text_width = content_rect.width.saturating_sub(6).max(1) as usize;
+    // Split the input into lines of at most `text_width` characters. The cursor may sit right
+    // after the last character, so that position needs to fit as well.
+    let chars: Vec<char> = popup_core.input.chars().collect();
+    let line_count = chars.len() / text_width + 1;
+    let popup_area = get_input_popup_rect(content_rect, (line_count + footer.len()) as u16);
+    let mut lines: Vec<Line> = chars
+        .chunks(text_width)
+        .map(|chunk| {
+            Line::from(Span::styled(
+                chunk.iter().collect::<String>(),
+                Style::default().fg(config.colors.popup_text),
+            ))
+        })
+        .collect();
+    // The cursor may need a line of its own, e.g. when the input is empty.
+    while lines.len() < line_count {
+        lines.push(Line::from(""));
+    }
+    lines.extend(footer);
+    let block = Block::new()
+        .title(title.to_string())
+        .title_alignment(Alignment::Left)
+        .title_style(Style::default().fg(config.colors.popup_block_title))
+        .border_type(BorderType::Rounded)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(config.colors.popup_block_border));
+    let popup_par = Paragraph::new(lines)
+        .block(block)
+        .alignment(Alignment::Left);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup_par, popup_area);
+    // Place the cursor, making sure it stays inside the pop-up.
+    let cursor_x = popup_area.x + 1 + (popup_core.char_index % text_width) as u16;
+    let cursor_y = popup_area.y + 1 + (popup_core.char_index / text_width) as u16;
+    if cursor_y < popup_area.y + popup_area.height.saturating_sub(1) {
+        frame.set_cursor(cursor_x, cursor_y);
+    }
+    return popup_area;
+}
+
+/// Define the location of a text input pop-up window with `line_count` lines of text. It spans
+/// the width of the `content_rect`, minus the margin, and is as high as needed to show all the
+/// lines, bounded by the height of the `content_rect`.
+pub fn get_input_popup_rect(content_rect: Rect, line_count: u16) -> Rect {
+    let x: u16 = content_rect.x + 2;
+    let y: u16 = content_rect.y + 2;
+    let width: u16 = content_rect.width.saturating_sub(4);
+    // +2 for the top and bottom border.
+    let height: u16 = (line_count + 2).min(content_rect.height.saturating_sub(4));
+    return Rect::new(x, y, width, height).intersection(content_rect);
+}
+
+/// Define the location of the help overlay, centered in the `frame_rect` and
+/// covering most of it.
+pub fn get_help_rect(frame_rect: Rect) -> Rect {
+    let width: u16 = (frame_rect.width * 4 / 5).max(frame_rect.width.min(40));
+    let height: u16 = (frame_rect.height * 4 / 5).max(frame_rect.height.min(10));
+    let x: u16 = frame_rect.x + (frame_rect.width - width) / 2;
+    let y: u16 = frame_rect.y + (frame_rect.height - height) / 2;
+    return Rect::new(x, y, width, height);
+}
+
+/// Define the location of the pop-up window, next to the currently selected paper. The
+/// pop-up is put to the right of the explorer if it fits inside the `frame_rect`, and on top of
+/// the explorer otherwise, e.g. in the stacked layout.
+pub fn get_popup_rect(selected_idx: usize, explorer_rect: Rect, frame_rect: Rect) -> Rect {
+    // Set the width of the pop-up box equal to 75% the width of the explorer.
+    let width: u16 = 3 * explorer_rect.width / 4;
     // Use the width of the explorer window to set the x location of the rectangle.
-    let x: u16 = explorer_rect.width;
+    let x: u16 = if explorer_rect.right() + width <= frame_rect.right() {
+        explorer_rect.right()
+    } else {
+        explorer_rect.x + explorer_rect.width / 8
+    };
     // Set the y location of the rectangle at the current selected file, for this we need
     // to pad with +2 due to margin, title, etc.
-    let y: u16 = selected_idx as u16 + 2;
-    // Set the width of the pop-up box equal to 75% the width of the explorer.
-    let width: u16 = 3 * explorer_rect.width / 4;
+    let y: u16 = explorer_rect.y + selected_idx as u16 + 2;
     // Set the height to 3, which is the minimum we need for a simply y/n box.
     let height: u16 = 3;
-    return Rect::new(x, y, width, height);
+    // Keep the pop-up inside the terminal, e.g. after it was resized.
+    return Rect::new(x, y, width, height).clamp(frame_rect);
 }
 /// Pad the back of a string with non-breaking space character. This allows us
 /// to remove the background from underlying widgets which are visible even when
@@ -1,19 +1,72 @@
+use super::help;
+use super::layout::loader_size_for_area;
+use super::status::{self, StatusMessage};
+use super::tags::TagBrowser;
 use super::ui_wrapper;
-use crate::configuration::config::Config;
-use crate::file::loader::Loader;
+use crate::args::parser::NAME;
+use crate::configuration::config::{Config, ContentPane};
+use crate::file::library::Library;
+use crate::file::loader::{ActionResult, Loader};
+use crate::file::parser::{PaperField, READING_STATUSES};
+use crate::file::watcher::LibraryWatcher;
 use crate::key::event;
+use crate::listing::tags::get_unique_tags;
 use crate::popup::popup;
+use ratatui::layout::{Position, Rect};
+use std::cell::Cell;
 
 /// Define the possible pop-up windows.
 pub enum PopupState {
     NoPopup,
     ConfirmDelete,
+    SelectField,
+    EditField(PaperField),
+    EditTags,
+    SetStatus,
+    ExportBibtex,
+    GoToIndex,
+    SwitchLibrary,
+    Help,
 }
+/// Areas of the terminal the last drawn frame put the explorer, the
+/// description and the pop-up window in. These are used to find out
+/// what was clicked on with the mouse.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderedAreas {
+    pub explorer: Rect,
+    pub description: Rect,
+    pub tags: Rect,
+    pub popup: Option<Rect>,
+}
+
+impl RenderedAreas {
+    /// Check if the terminal cell at `column` and `row` is inside `area`.
+    pub fn contains(area: Rect, column: u16, row: u16) -> bool {
+        area.contains(Position::new(column, row))
+    }
+}
+
 /// Store if there is a pop-up open at the moment or not, and information
-/// about the content of that pop-up.
+/// about the content of that pop-up. The `completions` are offered when
+/// pressing Tab inside the pop-up. The `visual_anchor` is the index in
+/// `Loader.valid_paths` where the current visual range selection started,
+/// if there is one. The `description_scroll` is the amount of lines the
+/// description is scrolled down, and `help_scroll` the same for the help
+/// overlay. The `status` is the message shown in the status bar, if any.
+/// The `tag_browser` holds the state of the tag browser in the tags block,
+/// and `show_bibtex` tells if the bibtex pane is shown.
+/// The `rendered_areas` are set while drawing, hence the `Cell`.
 pub struct AppState {
     pub popup_state: PopupState,
     pub popup_core: popup::PopupCore,
+    pub completions: Vec<String>,
+    pub visual_anchor: Option<usize>,
+    pub description_scroll: u16,
+    pub help_scroll: u16,
+    pub status: Option<StatusMessage>,
+    pub tag_browser: TagBrowser,
+    pub show_bibtex: bool,
+    pub rendered_areas: Cell<RenderedAreas>,
 }
 
 impl Default for AppState {
@@ -22,14 +75,61 @@ impl Default for AppState {
         Self {
             popup_state: PopupState::NoPopup,
             popup_core: popup::PopupCore::default(),
+            completions: Vec::new(),
+            visual_anchor: None,
+            description_scroll: 0,
+            help_scroll: 0,
+            status: None,
+            tag_browser: TagBrowser::default(),
+            show_bibtex: false,
+            rendered_areas: Cell::new(RenderedAreas::default()),
         }
     }
 }
 
 impl AppState {
     /// Method for setting a given [`AppState`] instance back to its default.
+    /// The status message, the tag browser and the bibtex pane are kept, so
+    /// they stay visible after a pop-up closes.
     pub fn set_default(&mut self) {
+        let status = self.status.take();
+        let tag_browser = std::mem::take(&mut self.tag_browser);
+        let show_bibtex = self.show_bibtex;
         *self = Self::default();
+        self.status = status;
+        self.tag_browser = tag_browser;
+        self.show_bibtex = show_bibtex;
+    }
+    /// Show the outcome of an action in the status bar, and as a desktop
+    /// notification if those are enabled in the configuration.
+    pub fn report(&mut self, result: ActionResult, config: &Config) {
+        self.show_status(result, config.general.desktop_notifications);
+    }
+    /// Show the outcome of copying to the clipboard in the status bar, and as a desktop
+    /// notification if those are enabled for all messages or for clipboard copies.
+    pub fn report_clipboard(&mut self, result: ActionResult, config: &Config) {
+        let notify = config.general.desktop_notifications || config.general.clipboard_notifications;
+        self.show_status(result, notify);
+    }
+    /// Show the outcome of an action in the status bar, and as a desktop notification if
+    /// `notify` is set.
+    fn show_status(&mut self, result: ActionResult, notify: bool) {
+        let message = StatusMessage::from_result(result);
+        if notify {
+            status::send_notification(&message.text);
+        }
+        self.status = Some(message);
+    }
+    /// Mark all papers in the visual range selection, if there is one, and end
+    /// the visual range selection. This is done before any bulk action, so the
+    /// action is applied to the visual range as well.
+    pub fn commit_visual_range(&mut self, file_load: &mut Loader, file_pointer: usize) {
+        if let (Some(anchor), Some(current)) =
+            (self.visual_anchor, file_load.path_index(file_pointer))
+        {
+            file_load.mark_range(anchor, current);
+        }
+        self.visual_anchor = None;
     }
 }
 
@@ -55,6 +155,13 @@ pub fn create_window(file_load: &mut Loader, config: &Config) {
             std::process::exit(1);
         }
     }
+    // Capture the mouse, so clicks and scrolling can be handled
+    match crossterm::execute!(std::io::stdout(), crossterm::event::EnableMouseCapture) {
+        Ok(_) => {}
+        Err(err) => {
+            log::warn!("Error enabling mouse capture, the mouse will not work: {err}");
+        }
+    }
     // Define ratatui terminal interface with crossterm
     let mut terminal =
         match ratatui::Terminal::new(ratatui::backend::CrosstermBackend::new(std::io::stdout())) {
@@ -68,9 +175,61 @@ pub fn create_window(file_load: &mut Loader, config: &Config) {
     // Define UI drawing loop
     let mut run = true;
     let mut file_pointer: usize = 0;
-    let mut app_state: AppState = AppState::default();
+    let mut app_state: AppState = AppState {
+        show_bibtex: config.layout.content_panes.contains(&ContentPane::Bibtex),
+        ..Default::default()
+    };
+    // Warn if some of the configuration file could not be applied.
+    if !config.diagnostics.is_empty() {
+        app_state.report(
+            Err(format!(
+                "Configuration not fully applied, {} problem(s) found, run `{} --check-config` for details",
+                config.diagnostics.len(),
+                NAME
+            )),
+            config,
+        );
+    }
+    // Watch the paper files, so changes made outside the TUI show up.
+    let mut watcher = if config.general.live_reload {
+        LibraryWatcher::new(&file_load.folderdir)
+    } else {
+        None
+    };
+
+    let mut last_selected: Option<usize> = None;
+    let mut key_dispatcher = event::KeyDispatcher::default();
 
     while run {
+        if let Some(w) = &watcher {
+            if w.has_changes() {
+                file_pointer = file_load.reload(file_pointer);
+            }
+        }
+        // Fit the loaded papers to the explorer again whenever the terminal was resized.
+        match terminal.size() {
+            Ok(size) => {
+                let load_size = loader_size_for_area(size, config);
+                if load_size != file_load.load_size {
+                    file_pointer = file_load.set_load_size(load_size, file_pointer);
+                }
+            }
+            Err(err) => {
+                log::warn!("Error obtaining the terminal size: {err}");
+            }
+        }
+        // Remove the status message once it has been shown long enough.
+        if let Some(message) = &app_state.status {
+            if message.is_expired(config.general.status_timeout) {
+                app_state.status = None;
+            }
+        }
+        // Start at the top of the description whenever another paper is selected.
+        let selected = file_load.path_index(file_pointer);
+        if selected != last_selected {
+            app_state.description_scroll = 0;
+            last_selected = selected;
+        }
         let ui = ui_wrapper::ui_pre_args(file_load, config, &app_state, file_pointer);
         match terminal.draw(ui) {
             Ok(_) => {}
@@ -81,7 +240,15 @@ pub fn create_window(file_load: &mut Loader, config: &Config) {
         }
         match app_state.popup_state {
             PopupState::NoPopup => {
-                let key_event: event::KeyEvents = event::get_key_event(config);
+                let mut key_event: event::KeyEvents = key_dispatcher.get_key_event(config);
+                if app_state.tag_browser.active {
+                    key_event = handle_tag_browser_event(
+                        key_event,
+                        &mut app_state,
+                        file_load,
+                        &mut file_pointer,
+                    );
+                }
                 match key_event {
                     event::KeyEvents::Quit => {
                         run = false;
@@ -92,22 +259,196 @@ pub fn create_window(file_load: &mut Loader, config: &Config) {
                     event::KeyEvents::Previous => {
                         file_pointer = file_load.load_previous(file_pointer);
                     }
+                    event::KeyEvents::PageDown => {
+                        let page = file_load.window_len() as isize;
+                        file_pointer = file_load.move_by(file_pointer, page);
+                    }
+                    event::KeyEvents::PageUp => {
+                        let page = file_load.window_len() as isize;
+                        file_pointer = file_load.move_by(file_pointer, -page);
+                    }
+                    event::KeyEvents::HalfPageDown => {
+                        let half_page = (file_load.window_len() as isize / 2).max(1);
+                        file_pointer = file_load.move_by(file_pointer, half_page);
+                    }
+                    event::KeyEvents::HalfPageUp => {
+                        let half_page = (file_load.window_len() as isize / 2).max(1);
+                        file_pointer = file_load.move_by(file_pointer, -half_page);
+                    }
+                    event::KeyEvents::GoToTop => {
+                        file_pointer = file_load.select(0, false);
+                    }
+                    event::KeyEvents::GoToBottom => {
+                        file_pointer = file_load.select(usize::MAX, true);
+                    }
+                    event::KeyEvents::GoToIndex => {
+                        app_state.popup_state = PopupState::GoToIndex;
+                    }
                     event::KeyEvents::Bibtex => {
-                        file_load.bibtex_entry_to_clipboard(file_pointer);
+                        let result = file_load.bibtex_entry_to_clipboard(file_pointer);
+                        app_state.report_clipboard(result, config);
                     }
                     event::KeyEvents::Edit => {
-                        file_load.open_file_in_editor(file_pointer, &config.general.editor_command);
+                        let result = file_load
+                            .open_file_in_editor(file_pointer, &config.general.editor_command);
+                        app_state.report(result, config);
+                    }
+                    event::KeyEvents::EditField => {
+                        app_state.popup_state = PopupState::SelectField;
+                    }
+                    event::KeyEvents::EditTags => {
+                        app_state.commit_visual_range(file_load, file_pointer);
+                        app_state.popup_state = PopupState::EditTags;
+                        app_state.completions =
+                            get_unique_tags(&file_load.folderdir, &file_load.tag_normalizer);
                     }
                     event::KeyEvents::Delete => {
-                        //file_load.remove_file(file_pointer);
+                        app_state.commit_visual_range(file_load, file_pointer);
                         app_state.popup_state = PopupState::ConfirmDelete;
                     }
+                    event::KeyEvents::Click(column, row) => {
+                        // Select the clicked title. The +1 skips the border of the explorer.
+                        let explorer = app_state.rendered_areas.get().explorer;
+                        if RenderedAreas::contains(explorer, column, row) {
+                            let clicked = row.saturating_sub(explorer.y + 1) as usize;
+                            if row > explorer.y && clicked < file_load.papers.len() {
+                                file_pointer = clicked;
+                            }
+                        }
+                    }
+                    event::KeyEvents::ScrollUp(column, row) => {
+                        let areas = app_state.rendered_areas.get();
+                        if RenderedAreas::contains(areas.explorer, column, row) {
+                            file_pointer = file_load.load_previous(file_pointer);
+                        } else if RenderedAreas::contains(areas.description, column, row) {
+                            app_state.description_scroll =
+                                app_state.description_scroll.saturating_sub(1);
+                        }
+                    }
+                    event::KeyEvents::ScrollDown(column, row) => {
+                        let areas = app_state.rendered_areas.get();
+                        if RenderedAreas::contains(areas.explorer, column, row) {
+                            file_pointer = file_load.load_next(file_pointer);
+                        } else if RenderedAreas::contains(areas.description, column, row) {
+                            app_state.description_scroll =
+                                app_state.description_scroll.saturating_add(1);
+                        }
+                    }
+                    event::KeyEvents::UndoDelete => {
+                        let removed_count = file_load.last_removed.len();
+                        if removed_count == 0 {
+                            app_state.report(Err(String::from("Nothing to undo")), config);
+                        } else {
+                            let restored;
+                            (file_pointer, restored) = file_load.undo_remove(file_pointer);
+                            let result = if restored == removed_count {
+                                Ok(format!("Restored {} paper(s) from the trash", restored))
+                            } else {
+                                Err(format!(
+                                    "Restored {} of {} paper(s) from the trash",
+                                    restored, removed_count
+                                ))
+                            };
+                            app_state.report(result, config);
+                        }
+                    }
+                    event::KeyEvents::ToggleMark => {
+                        if let Some(path_idx) = file_load.path_index(file_pointer) {
+                            file_load.toggle_mark(path_idx);
+                        }
+                    }
+                    event::KeyEvents::VisualMark => {
+                        // Start a visual range selection, or mark the range if one was started.
+                        if app_state.visual_anchor.is_some() {
+                            app_state.commit_visual_range(file_load, file_pointer);
+                        } else {
+                            app_state.visual_anchor = file_load.path_index(file_pointer);
+                        }
+                    }
+                    event::KeyEvents::ClearMarks => {
+                        app_state.visual_anchor = None;
+                        file_load.marked.clear();
+                    }
+                    event::KeyEvents::SetStatus => {
+                        app_state.commit_visual_range(file_load, file_pointer);
+                        app_state.popup_state = PopupState::SetStatus;
+                        app_state.completions =
+                            READING_STATUSES.iter().map(|s| s.to_string()).collect();
+                    }
+                    event::KeyEvents::CopyCitationKeys => {
+                        app_state.commit_visual_range(file_load, file_pointer);
+                        let result = file_load
+                            .citation_keys_to_clipboard(&file_load.target_indices(file_pointer));
+                        app_state.report_clipboard(result, config);
+                    }
+                    event::KeyEvents::ExportBibtex => {
+                        app_state.commit_visual_range(file_load, file_pointer);
+                        app_state.popup_state = PopupState::ExportBibtex;
+                    }
+                    event::KeyEvents::TagBrowser => {
+                        if !config.layout.show_tags {
+                            app_state.report(
+                                Err(String::from(
+                                    "The tag browser needs the tags pane, which is hidden in the configuration file",
+                                )),
+                                config,
+                            );
+                        } else {
+                            app_state.tag_browser.open(
+                                &file_load.folderdir,
+                                &file_load.tag_filters,
+                                &file_load.tag_normalizer,
+                            );
+                        }
+                    }
+                    event::KeyEvents::ToggleBibtex => {
+                        app_state.show_bibtex = !app_state.show_bibtex;
+                    }
+                    event::KeyEvents::SwitchLibrary => {
+                        if config.libraries.is_empty() {
+                            app_state.report(
+                                Err(String::from(
+                                    "No libraries are set in the configuration file",
+                                )),
+                                config,
+                            );
+                        } else {
+                            app_state.popup_state = PopupState::SwitchLibrary;
+                            app_state.completions = config.libraries.keys().cloned().collect();
+                        }
+                    }
+                    event::KeyEvents::Help => {
+                        app_state.popup_state = PopupState::Help;
+                        app_state.help_scroll = 0;
+                    }
                     event::KeyEvents::Open => {
-                        file_load.open_file_in_pdfviewer(
+                        let result = file_load.open_file_in_pdfviewer(
                             file_pointer,
                             &config.general.pdf_viewer,
-                            &config.general.pdf_dir,
+                            &file_load.pdf_dir,
                         );
+                        app_state.report(result, config);
+                    }
+                    _ => {}
+                }
+            }
+            PopupState::Help => {
+                // The help is read with the normal key binds, so scrolling uses the same keys
+                // as moving through the papers.
+                let line_count = help::render(config).len() as u16;
+                match key_dispatcher.get_key_event(config) {
+                    event::KeyEvents::Next | event::KeyEvents::ScrollDown(_, _) => {
+                        app_state.help_scroll =
+                            (app_state.help_scroll + 1).min(line_count.saturating_sub(1));
+                    }
+                    event::KeyEvents::Previous | event::KeyEvents::ScrollUp(_, _) => {
+                        app_state.help_scroll = app_state.help_scroll.saturating_sub(1);
+                    }
+                    event::KeyEvents::Help
+                    | event::KeyEvents::Quit
+                    | event::KeyEvents::Cancel
+                    | event::KeyEvents::Click(_, _) => {
+                        app_state.set_default();
                     }
                     _ => {}
                 }
@@ -116,16 +457,226 @@ pub fn create_window(file_load: &mut Loader, config: &Config) {
                 popup::handle_key_events(&mut app_state);
                 if !app_state.popup_core.entered_message.is_empty() {
                     if app_state.popup_core.entered_message.trim() == "y" {
-                        // Only if the delete is confirmed, delete the selected file.
-                        file_load.remove_file(file_pointer);
+                        // Only if the delete is confirmed, delete the marked or selected files.
+                        let targets = file_load.target_indices(file_pointer);
+                        file_pointer = file_load.remove_files(&targets, file_pointer);
+                        let removed = file_load.last_removed.len();
+                        let result = if removed == targets.len() {
+                            Ok(format!("Moved {} paper(s) to the trash", removed))
+                        } else {
+                            Err(format!(
+                                "Moved {} of {} paper(s) to the trash",
+                                removed,
+                                targets.len()
+                            ))
+                        };
+                        app_state.report(result, config);
                     }
                     // In any circumstance, if the entered message is non-empty, remove popup.
                     app_state.set_default();
                 }
             }
+            PopupState::SelectField => {
+                popup::handle_key_events(&mut app_state);
+                if app_state.popup_core.submitted {
+                    let field = PaperField::from_name(&app_state.popup_core.entered_message);
+                    match (field, file_load.papers.get(file_pointer)) {
+                        (Some(f), Some(paper)) if f.is_multiline(paper) => {
+                            // The pop-up holds a single line, so open the whole paper file in
+                            // the editor instead. The watcher picks up the change once saved.
+                            let result = file_load
+                                .open_file_in_editor(file_pointer, &config.general.editor_command)
+                                .map(|_| format!("Edit the {} in the editor", f.name()));
+                            app_state.set_default();
+                            app_state.report(result, config);
+                        }
+                        (Some(f), Some(paper)) => {
+                            // Start editing from the current value of the field, with the
+                            // cursor placed at the end of it.
+                            let value = f.get(paper);
+                            let char_index = value.chars().count();
+                            app_state.popup_state = PopupState::EditField(f);
+                            app_state.popup_core =
+                                popup::PopupCore::new(value, char_index, String::new());
+                        }
+                        _ => {
+                            app_state.set_default();
+                            app_state.report(Err(String::from("Unknown field")), config);
+                        }
+                    }
+                }
+            }
+            PopupState::EditField(field) => {
+                popup::handle_key_events(&mut app_state);
+                if app_state.popup_core.submitted {
+                    let result = if file_load.edit_field(
+                        file_pointer,
+                        field,
+                        &app_state.popup_core.entered_message,
+                    ) {
+                        Ok(format!("Saved the {}", field.name()))
+                    } else {
+                        Err(format!("Error saving the {}", field.name()))
+                    };
+                    app_state.set_default();
+                    app_state.report(result, config);
+                }
+            }
+            PopupState::EditTags => {
+                popup::handle_key_events(&mut app_state);
+                if app_state.popup_core.submitted {
+                    // Use the spelling of the tag the library already has, if any.
+                    let label = file_load.tag_normalizer.canonical(
+                        &app_state.popup_core.entered_message,
+                        &app_state.completions,
+                    );
+                    if label.is_empty() {
+                        // Entering nothing closes the pop-up.
+                        app_state.set_default();
+                    } else {
+                        // Toggle the tag: remove it if all the targeted papers have it, add it to
+                        // all of them otherwise.
+                        let targets = file_load.target_indices(file_pointer);
+                        let all_have_tag = targets.iter().all(|&i| match file_load.get_paper(i) {
+                            Some(p) => p
+                                .tags
+                                .iter()
+                                .any(|t| file_load.tag_normalizer.same(&t.label, &label)),
+                            None => false,
+                        });
+                        let mut changed: usize = 0;
+                        for &path_idx in targets.iter() {
+                            let success = if all_have_tag {
+                                file_load.remove_tag(path_idx, &label)
+                            } else {
+                                file_load.add_tag(path_idx, &label)
+                            };
+                            if success {
+                                changed += 1;
+                            }
+                        }
+                        let action = if all_have_tag { "Removed" } else { "Added" };
+                        let result = if changed == targets.len() {
+                            Ok(format!("{} tag {} on {} paper(s)", action, label, changed))
+                        } else {
+                            Err(format!(
+                                "{} tag {} on {} of {} paper(s)",
+                                action,
+                                label,
+                                changed,
+                                targets.len()
+                            ))
+                        };
+                        app_state.report(result, config);
+                        if !all_have_tag && !app_state.completions.contains(&label) {
+                            app_state.completions.push(label);
+                            app_state.completions.sort();
+                        }
+                        // Keep the pop-up open so more tags can be added or removed.
+                        app_state.popup_core = popup::PopupCore::default();
+                    }
+                }
+            }
+            PopupState::SetStatus => {
+                popup::handle_key_events(&mut app_state);
+                if app_state.popup_core.submitted {
+                    let status = app_state.popup_core.entered_message.trim().to_string();
+                    let targets = file_load.target_indices(file_pointer);
+                    let changed = targets
+                        .iter()
+                        .filter(|&&i| file_load.edit_field_at(i, PaperField::Status, &status))
+                        .count();
+                    let result = if changed == targets.len() {
+                        Ok(format!(
+                            "Set the status of {} paper(s) to {}",
+                            changed, status
+                        ))
+                    } else {
+                        Err(format!(
+                            "Set the status of {} of {} paper(s) to {}",
+                            changed,
+                            targets.len(),
+                            status
+                        ))
+                    };
+                    app_state.set_default();
+                    app_state.report(result, config);
+                }
+            }
+            PopupState::GoToIndex => {
+                popup::handle_key_events(&mut app_state);
+                if app_state.popup_core.submitted {
+                    // The index is shown to the user starting from one.
+                    match app_state.popup_core.entered_message.trim().parse::<usize>() {
+                        Ok(n) => {
+                            file_pointer = file_load.select(n.saturating_sub(1), false);
+                        }
+                        Err(err) => {
+                            log::warn!("Unable to parse the entered paper index: {err}");
+                            app_state.report(Err(format!("Invalid paper number: {err}")), config);
+                        }
+                    }
+                    app_state.set_default();
+                }
+            }
+            PopupState::SwitchLibrary => {
+                popup::handle_key_events(&mut app_state);
+                if app_state.popup_core.submitted {
+                    let name = app_state.popup_core.entered_message.trim().to_string();
+                    app_state.set_default();
+                    let result = match Library::from_name(&name, config) {
+                        Some(library) => match library.create_dir() {
+                            Ok(_) => {
+                                // Start over in the other library, the tag filters and marks
+                                // do not carry over.
+                                *file_load = Loader::load(
+                                    file_load.load_size,
+                                    &library,
+                                    &None,
+                                    &file_load.tag_normalizer,
+                                );
+                                file_pointer = 0;
+                                app_state.tag_browser = TagBrowser::default();
+                                if config.general.live_reload {
+                                    watcher = LibraryWatcher::new(&file_load.folderdir);
+                                }
+                                Ok(format!(
+                                    "Switched to library {} ({} papers)",
+                                    name,
+                                    file_load.valid_paths.len()
+                                ))
+                            }
+                            Err(err) => Err(err),
+                        },
+                        None => Err(format!("Unknown library {}", name)),
+                    };
+                    app_state.report(result, config);
+                }
+            }
+            PopupState::ExportBibtex => {
+                popup::handle_key_events(&mut app_state);
+                if app_state.popup_core.submitted {
+                    let file_path = app_state.popup_core.entered_message.trim().to_string();
+                    app_state.set_default();
+                    if !file_path.is_empty() {
+                        let result = file_load.export_bibtex(
+                            &file_load.target_indices(file_pointer),
+                            &std::path::PathBuf::from(file_path),
+                        );
+                        app_state.report(result, config);
+                    }
+                }
+            }
         }
     }
 
+    // Release the mouse
+    match crossterm::execute!(std::io::stdout(), crossterm::event::DisableMouseCapture) {
+        Ok(_) => {}
+        Err(err) => {
+            log::warn!("Error disabling mouse capture: {err}");
+        }
+    }
     // Disable raw mode so we return to normal terminal function
     match crossterm::terminal::disable_raw_mode() {
         Ok(_) => {}
@@ -143,3 +694,63 @@ pub fn create_window(file_load: &mut Loader, config: &Config) {
         }
     }
 }
+
+/// Handle the `key_event` while the tag browser has the focus. Moving up and down
+/// moves through the tags, marking a paper selects or deselects the tag under the
+/// cursor instead, and the papers are filtered on the selected tags right away.
+/// Events that do not concern the tag browser are returned, so they are handled
+/// as usual, all other events are turned into `KeyEvents::NoEvent`.
+fn handle_tag_browser_event(
+    key_event: event::KeyEvents,
+    app_state: &mut AppState,
+    file_load: &mut Loader,
+    file_pointer: &mut usize,
+) -> event::KeyEvents {
+    let tags_area = app_state.rendered_areas.get().tags;
+    // Amount of tags visible inside the borders of the tags block.
+    let page = tags_area.height.saturating_sub(2).max(1) as isize;
+    let browser = &mut app_state.tag_browser;
+    match key_event {
+        event::KeyEvents::Next => browser.move_cursor(1),
+        event::KeyEvents::Previous => browser.move_cursor(-1),
+        event::KeyEvents::PageDown | event::KeyEvents::HalfPageDown => browser.move_cursor(page),
+        event::KeyEvents::PageUp | event::KeyEvents::HalfPageUp => browser.move_cursor(-page),
+        event::KeyEvents::GoToTop => browser.move_cursor(isize::MIN / 2),
+        event::KeyEvents::GoToBottom => browser.move_cursor(isize::MAX / 2),
+        event::KeyEvents::CollapseTag => browser.collapse(),
+        event::KeyEvents::ExpandTag => browser.expand(),
+        event::KeyEvents::ToggleMark => {
+            let filters =
+                browser.toggled_filters(&file_load.tag_filters, &file_load.tag_normalizer);
+            *file_pointer = file_load.set_tag_filters(filters, *file_pointer);
+        }
+        event::KeyEvents::ClearMarks => {
+            *file_pointer = file_load.set_tag_filters(Vec::new(), *file_pointer);
+        }
+        event::KeyEvents::TagBrowser | event::KeyEvents::Cancel => browser.active = false,
+        event::KeyEvents::Click(column, row) if RenderedAreas::contains(tags_area, column, row) => {
+            // Select the clicked tag. The +1 skips the border of the tags block.
+            if row > tags_area.y {
+                let clicked = browser.offset(tags_area) + (row - tags_area.y - 1) as usize;
+                if clicked < browser.visible().len() {
+                    browser.cursor = clicked;
+                    let filters =
+                        browser.toggled_filters(&file_load.tag_filters, &file_load.tag_normalizer);
+                    *file_pointer = file_load.set_tag_filters(filters, *file_pointer);
+                }
+            }
+        }
+        event::KeyEvents::ScrollUp(column, row)
+            if RenderedAreas::contains(tags_area, column, row) =>
+        {
+            browser.move_cursor(-1);
+        }
+        event::KeyEvents::ScrollDown(column, row)
+            if RenderedAreas::contains(tags_area, column, row) =>
+        {
+            browser.move_cursor(1);
+        }
+        _ => return key_event,
+    }
+    return event::KeyEvents::NoEvent;
+}